@@ -0,0 +1,376 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use egui::Color32;
+use image::{imageops, Pixel, Rgba, RgbaImage};
+
+use crate::palette::{from_hex, to_hex};
+
+//Frame drawn around a capture on export: padding on a solid or gradient background,
+//rounded corners, a border and a drop shadow. Sizes are in pixels of the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beautify {
+    pub enabled: bool,
+    pub padding: u32,
+    pub gradient: bool,
+    pub background: Color32,
+    pub background_end: Color32,
+    pub corner_radius: u32,
+    pub border_width: u32,
+    pub border_color: Color32,
+    pub shadow_blur: u32,
+    pub shadow_offset: u32,
+    pub shadow_opacity: u8,
+}
+
+impl Default for Beautify {
+    fn default() -> Self {
+        Beautify {
+            enabled: false,
+            padding: 48,
+            gradient: true,
+            background: Color32::from_rgb(94, 114, 235),
+            background_end: Color32::from_rgb(255, 145, 144),
+            corner_radius: 12,
+            border_width: 0,
+            border_color: Color32::WHITE,
+            shadow_blur: 16,
+            shadow_offset: 8,
+            shadow_opacity: 110,
+        }
+    }
+}
+
+impl Beautify {
+    pub fn apply(&self, image: &RgbaImage) -> RgbaImage {
+        let content = self.framed_content(image);
+        let (cw, ch) = content.dimensions();
+        let width = cw + 2 * self.padding;
+        let height = ch + 2 * self.padding;
+
+        let mut canvas = RgbaImage::from_fn(width, height, |_, y| {
+            if self.gradient && height > 1 {
+                lerp(self.background, self.background_end, y as f32 / (height - 1) as f32)
+            } else {
+                to_rgba(self.background)
+            }
+        });
+
+        if self.shadow_opacity > 0 {
+            let mut shadow = RgbaImage::new(width, height);
+            let offset = self.padding + self.shadow_offset;
+            for (x, y, p) in content.enumerate_pixels() {
+                if x + offset < width && y + offset < height {
+                    let alpha = p.0[3] as u32 * self.shadow_opacity as u32 / 255;
+                    shadow.put_pixel(x + offset, y + offset, Rgba([0, 0, 0, alpha as u8]));
+                }
+            }
+            if self.shadow_blur > 0 {
+                shadow = imageops::blur(&shadow, self.shadow_blur as f32 / 2.0);
+            }
+            imageops::overlay(&mut canvas, &shadow, 0, 0);
+        }
+
+        imageops::overlay(&mut canvas, &content, self.padding as i64, self.padding as i64);
+        canvas
+    }
+
+    //The capture with its border around it and both corners rounded
+    fn framed_content(&self, image: &RgbaImage) -> RgbaImage {
+        let (w, h) = image.dimensions();
+        let b = self.border_width;
+        let (cw, ch) = (w + 2 * b, h + 2 * b);
+        let inner_radius = self.corner_radius as f32;
+        let outer_radius = inner_radius + if inner_radius > 0.0 { b as f32 } else { 0.0 };
+        let border = to_rgba(self.border_color);
+
+        RgbaImage::from_fn(cw, ch, |x, y| {
+            let mut p = Rgba([0, 0, 0, 0]);
+            if b > 0 {
+                p = border;
+                p.0[3] = (p.0[3] as f32 * rounded_coverage(x, y, cw, ch, outer_radius)) as u8;
+            }
+            if x >= b && y >= b && x < b + w && y < b + h {
+                let mut pixel = *image.get_pixel(x - b, y - b);
+                pixel.0[3] = (pixel.0[3] as f32 * rounded_coverage(x - b, y - b, w, h, inner_radius)) as u8;
+                p.blend(&pixel);
+            }
+            p
+        })
+    }
+
+    //one line of the presets file: the name and then every value separated by tabs
+    fn to_line(self, name: &str) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            name,
+            self.padding,
+            self.gradient as u8,
+            to_hex(self.background),
+            to_hex(self.background_end),
+            self.corner_radius,
+            self.border_width,
+            to_hex(self.border_color),
+            self.shadow_blur,
+            self.shadow_offset,
+            self.shadow_opacity
+        )
+    }
+
+    fn from_line(line: &str) -> Option<(String, Self)> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 11 || fields[0].is_empty() {
+            return None;
+        }
+        let settings = Beautify {
+            enabled: true,
+            padding: fields[1].parse().ok()?,
+            gradient: fields[2] == "1",
+            background: from_hex(fields[3])?,
+            background_end: from_hex(fields[4])?,
+            corner_radius: fields[5].parse().ok()?,
+            border_width: fields[6].parse().ok()?,
+            border_color: from_hex(fields[7])?,
+            shadow_blur: fields[8].parse().ok()?,
+            shadow_offset: fields[9].parse().ok()?,
+            shadow_opacity: fields[10].parse().ok()?,
+        };
+        Some((fields[0].to_string(), settings))
+    }
+}
+
+fn to_rgba(c: Color32) -> Rgba<u8> {
+    Rgba(c.to_srgba_unmultiplied())
+}
+
+fn lerp(from: Color32, to: Color32, t: f32) -> Rgba<u8> {
+    let (a, b) = (from.to_srgba_unmultiplied(), to.to_srgba_unmultiplied());
+    Rgba(std::array::from_fn(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t).round() as u8))
+}
+
+//How much of the pixel lies inside a w x h rectangle with corners rounded by `radius`,
+//from 0 to 1 so that the edge of the corners is antialiased
+fn rounded_coverage(x: u32, y: u32, w: u32, h: u32, radius: f32) -> f32 {
+    let r = radius.min(w.min(h) as f32 / 2.0);
+    if r <= 0.0 {
+        return 1.0;
+    }
+    let px = x as f32 + 0.5;
+    let py = y as f32 + 0.5;
+    let cx = px.clamp(r, w as f32 - r);
+    let cy = py.clamp(r, h as f32 - r);
+    let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+    (r - distance + 0.5).clamp(0.0, 1.0)
+}
+
+//Named beautify settings, kept in a file next to the history
+pub struct BeautifyPresets {
+    path: PathBuf,
+    presets: Vec<(String, Beautify)>,
+}
+
+impl BeautifyPresets {
+    //a missing or unreadable file simply gives no presets
+    pub fn load(path: &Path) -> Self {
+        let presets = std::fs::read_to_string(path)
+            .map(|content| content.lines().filter_map(Beautify::from_line).collect())
+            .unwrap_or_default();
+        BeautifyPresets {
+            path: path.to_path_buf(),
+            presets,
+        }
+    }
+
+    pub fn get_presets(&self) -> &[(String, Beautify)] {
+        &self.presets
+    }
+
+    //a preset with the same name is replaced. Tabs and line breaks would split the
+    //line of the preset in the file, names with them are refused
+    pub fn add(&mut self, name: &str, settings: Beautify) -> std::io::Result<()> {
+        if name.is_empty() || name.contains(['\t', '\n', '\r']) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the name can not be empty or contain tabs and line breaks",
+            ));
+        }
+        match self.presets.iter_mut().find(|(n, _)| n == name) {
+            Some(preset) => preset.1 = settings,
+            None => self.presets.push((name.to_string(), settings)),
+        }
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> std::io::Result<()> {
+        self.presets.retain(|(n, _)| n != name);
+        self.save()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = std::fs::File::create(&self.path)?;
+        for (name, settings) in self.presets.iter() {
+            writeln!(file, "{}", settings.to_line(name))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("beautify_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("beautify.tsv")
+    }
+
+    fn plain() -> Beautify {
+        Beautify {
+            enabled: true,
+            padding: 10,
+            gradient: false,
+            background: Color32::from_rgb(0, 0, 255),
+            corner_radius: 0,
+            border_width: 0,
+            shadow_opacity: 0,
+            ..Beautify::default()
+        }
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        let path = temp_file("round_trip");
+        let custom = Beautify {
+            enabled: true,
+            padding: 3,
+            gradient: false,
+            background: Color32::from_rgb(1, 2, 3),
+            background_end: Color32::from_rgba_unmultiplied(250, 128, 0, 200),
+            corner_radius: 0,
+            border_width: 7,
+            border_color: Color32::from_rgb(9, 8, 7),
+            shadow_blur: 0,
+            shadow_offset: 31,
+            shadow_opacity: 255,
+        };
+        let mut presets = BeautifyPresets::load(&path);
+        presets.add("Default look", Beautify { enabled: true, ..Beautify::default() }).unwrap();
+        presets.add("ünïcode, spaces & | pipes", custom).unwrap();
+        presets.add("Default look", custom).unwrap();
+
+        let loaded = BeautifyPresets::load(&path);
+        assert_eq!(loaded.get_presets(), presets.get_presets());
+        assert_eq!(
+            loaded.get_presets(),
+            [("Default look".to_string(), custom), ("ünïcode, spaces & | pipes".to_string(), custom)]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn names_that_would_break_the_file_are_refused() {
+        let path = temp_file("refused");
+        let mut presets = BeautifyPresets::load(&path);
+        presets.add("kept", plain()).unwrap();
+        for name in ["", "with\ttab", "two\nlines", "carriage\rreturn"] {
+            let error = presets.add(name, Beautify::default()).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput, "{:?}", name);
+        }
+
+        let loaded = BeautifyPresets::load(&path);
+        assert_eq!(loaded.get_presets().len(), 1);
+        assert_eq!(loaded.get_presets()[0].0, "kept");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn apply_pads_the_capture_on_the_background() {
+        let image = RgbaImage::from_pixel(20, 10, RED);
+        let framed = plain().apply(&image);
+
+        assert_eq!(framed.dimensions(), (40, 30));
+        assert_eq!(*framed.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+        assert_eq!(*framed.get_pixel(9, 15), Rgba([0, 0, 255, 255]));
+        assert_eq!(*framed.get_pixel(10, 10), RED);
+        assert_eq!(*framed.get_pixel(29, 19), RED);
+        assert_eq!(*framed.get_pixel(30, 20), Rgba([0, 0, 255, 255]));
+
+        //the gradient goes from the first color on top to the second one at the bottom
+        let gradient = Beautify {
+            gradient: true,
+            background: Color32::BLACK,
+            background_end: Color32::WHITE,
+            ..plain()
+        }
+        .apply(&image);
+        assert_eq!(*gradient.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(*gradient.get_pixel(0, 29), Rgba([255, 255, 255, 255]));
+        assert!(gradient.get_pixel(0, 15)[0] > 100 && gradient.get_pixel(0, 15)[0] < 155);
+    }
+
+    #[test]
+    fn shadow_falls_below_and_right_of_the_capture() {
+        let image = RgbaImage::from_pixel(20, 10, RED);
+        let settings = Beautify {
+            background: Color32::WHITE,
+            shadow_blur: 0,
+            shadow_offset: 4,
+            shadow_opacity: 255,
+            ..plain()
+        };
+        let framed = settings.apply(&image);
+
+        //shifted by the offset, outside of the capture it darkens the background
+        assert_eq!(*framed.get_pixel(32, 22), Rgba([0, 0, 0, 255]));
+        assert_eq!(*framed.get_pixel(14, 22), Rgba([0, 0, 0, 255]));
+        assert_eq!(*framed.get_pixel(13, 22), Rgba([255, 255, 255, 255]));
+        assert_eq!(*framed.get_pixel(34, 24), Rgba([255, 255, 255, 255]));
+        assert_eq!(*framed.get_pixel(29, 19), RED);
+    }
+
+    #[test]
+    fn framed_content_adds_the_border_and_rounds_the_corners() {
+        let image = RgbaImage::from_pixel(30, 20, RED);
+        let settings = Beautify {
+            border_width: 2,
+            border_color: Color32::WHITE,
+            ..plain()
+        };
+        let framed = settings.framed_content(&image);
+        assert_eq!(framed.dimensions(), (34, 24));
+        for (x, y, p) in framed.enumerate_pixels() {
+            let inside = (2..32).contains(&x) && (2..22).contains(&y);
+            assert_eq!(*p, if inside { RED } else { Rgba([255, 255, 255, 255]) }, "pixel {},{}", x, y);
+        }
+
+        let rounded = Beautify {
+            corner_radius: 6,
+            ..settings
+        }
+        .framed_content(&image);
+        //the outer corner is cut away, the middle of the sides and the center are not
+        for (x, y) in [(0, 0), (33, 0), (0, 23), (33, 23)] {
+            assert_eq!(rounded.get_pixel(x, y)[3], 0, "corner {},{}", x, y);
+        }
+        assert_eq!(*rounded.get_pixel(17, 0), Rgba([255, 255, 255, 255]));
+        assert_eq!(*rounded.get_pixel(0, 12), Rgba([255, 255, 255, 255]));
+        assert_eq!(*rounded.get_pixel(17, 12), RED);
+        //the border follows the corner, the capture under it is rounded with the inner radius
+        assert!(rounded.get_pixel(3, 3)[3] > 0);
+        assert_ne!(*rounded.get_pixel(3, 3), RED);
+
+        //without a border the capture keeps its size
+        let bare = Beautify { corner_radius: 6, ..plain() }.framed_content(&image);
+        assert_eq!(bare.dimensions(), (30, 20));
+        assert_eq!(bare.get_pixel(0, 0)[3], 0);
+        assert_eq!(*bare.get_pixel(15, 10), RED);
+    }
+}
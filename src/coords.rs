@@ -0,0 +1,276 @@
+use egui::{Pos2, Rect, Vec2};
+
+//Every coordinate in the app is in one of three spaces:
+// - points: the logical units of egui, also used for the monitors layout as display_info gives it
+// - physical pixels of a screen: the units of the display server, of the pointer and of the windows
+// - image pixels: the pixels of an image showing an area of the screen, a capture or
+//   the texture drawn in the editor
+//Conversions between them go through this module, so that no other place needs
+//to know on which OS it is running
+
+//The display server itself works in physical pixels, display_info divides them by the
+//scale factor and truncates them to give the monitors in points. What comes straight
+//from the server, like the windows, is converted the same way to line up with them
+pub fn server_to_points(value: i32, scale_factor: f32) -> i32 {
+    (value as f32 / scale_factor) as i32
+}
+
+pub fn server_rect(x: i32, y: i32, width: u32, height: u32, scale_factor: f32) -> Rect {
+    Rect::from_min_size(
+        Pos2::new(server_to_points(x, scale_factor) as f32, server_to_points(y, scale_factor) as f32),
+        Vec2::new(
+            server_to_points(width as i32, scale_factor) as f32,
+            server_to_points(height as i32, scale_factor) as f32,
+        ),
+    )
+}
+
+//the pointer keeps the fraction of a point, it is not an edge
+pub fn server_pos(x: i32, y: i32, scale_factor: f32) -> Pos2 {
+    Pos2::new(x as f32 / scale_factor, y as f32 / scale_factor)
+}
+
+//The largest size with the proportions of `size` inside `bounds`, never larger than `size`
+pub fn fit(size: Vec2, bounds: Vec2) -> Vec2 {
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return size;
+    }
+    let factor = (bounds.x / size.x).min(bounds.y / size.y).clamp(0.0, 1.0);
+    size * factor
+}
+
+//Links an area of the screen (points) to the image that shows it. The scale comes
+//from the sizes themselves, so fractional and per-monitor scale factors need no
+//special case
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageMapping {
+    area: Rect,
+    width: u32,
+    height: u32,
+}
+
+impl ImageMapping {
+    pub fn new(area: Rect, width: u32, height: u32) -> Self {
+        ImageMapping { area, width, height }
+    }
+
+    //`area` on a screen at `scale_factor`, when no image of it has been grabbed yet
+    pub fn on_screen(area: Rect, scale_factor: f32) -> Self {
+        ImageMapping::new(
+            area,
+            (area.width() * scale_factor).round().max(0.0) as u32,
+            (area.height() * scale_factor).round().max(0.0) as u32,
+        )
+    }
+
+    //image pixels per point on each axis
+    pub fn scale(&self) -> Vec2 {
+        if self.area.width() <= 0.0 || self.area.height() <= 0.0 {
+            return Vec2::splat(1.0);
+        }
+        Vec2::new(
+            self.width as f32 / self.area.width(),
+            self.height as f32 / self.area.height(),
+        )
+    }
+
+    pub fn to_image(self, p: Pos2) -> Pos2 {
+        let scale = self.scale();
+        Pos2::new((p.x - self.area.min.x) * scale.x, (p.y - self.area.min.y) * scale.y)
+    }
+
+    pub fn to_area(self, p: Pos2) -> Pos2 {
+        let scale = self.scale();
+        Pos2::new(self.area.min.x + p.x / scale.x, self.area.min.y + p.y / scale.y)
+    }
+
+    //The pixels (x, y, width, height) covered by `rect`, clipped to the image.
+    //The edges are rounded one by one, so rectangles sharing a side in points
+    //share it in pixels too and no pixel is lost or repeated between them
+    pub fn pixel_rect(&self, rect: Rect) -> Option<(u32, u32, u32, u32)> {
+        let min = self.to_image(rect.min);
+        let max = self.to_image(rect.max);
+        let x0 = min.x.round().clamp(0.0, self.width as f32) as u32;
+        let y0 = min.y.round().clamp(0.0, self.height as f32) as u32;
+        let x1 = max.x.round().clamp(0.0, self.width as f32) as u32;
+        let y1 = max.y.round().clamp(0.0, self.height as f32) as u32;
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Deterministic pseudo-random numbers, so that a failure shows up again on every run
+    struct Numbers(u64);
+
+    impl Numbers {
+        fn new(seed: u64) -> Self {
+            Numbers(seed.max(1))
+        }
+
+        //from `from` included to `to` excluded
+        fn range(&mut self, from: f32, to: f32) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            from + (self.0 >> 40) as f32 / (1u64 << 24) as f32 * (to - from)
+        }
+    }
+
+    //the usual steps of the systems and as many scales in between
+    fn scales(numbers: &mut Numbers) -> Vec<f32> {
+        let mut scales: Vec<f32> = (2..=16).map(|quarter| quarter as f32 / 4.0).collect();
+        scales.extend((0..40).map(|_| numbers.range(0.5, 4.0)));
+        scales
+    }
+
+    //areas with fractional origin and size, anywhere around the primary monitor
+    fn areas(numbers: &mut Numbers) -> Vec<Rect> {
+        (0..40)
+            .map(|_| {
+                let origin = Pos2::new(numbers.range(-8000.0, 8000.0), numbers.range(-4000.0, 4000.0));
+                let size = Vec2::new(numbers.range(1.0, 4000.0), numbers.range(1.0, 2500.0));
+                Rect::from_min_size(origin, size)
+            })
+            .collect()
+    }
+
+    //the cuts splitting `from..to` in pieces of uneven, fractional sizes
+    fn cuts(numbers: &mut Numbers, from: f32, to: f32) -> Vec<f32> {
+        let mut cuts = vec![from];
+        loop {
+            let next = cuts.last().unwrap() + numbers.range(0.1, 120.0);
+            if next >= to {
+                break;
+            }
+            cuts.push(next);
+        }
+        cuts.push(to);
+        cuts
+    }
+
+    #[test]
+    fn adjacent_rects_share_their_edges() {
+        let mut numbers = Numbers::new(1);
+        for scale in scales(&mut numbers) {
+            for area in areas(&mut numbers) {
+                let mapping = ImageMapping::on_screen(area, scale);
+                let (width, height) = (mapping.width, mapping.height);
+
+                let xs = cuts(&mut numbers, area.min.x, area.max.x);
+                let columns: Vec<(u32, u32)> = xs
+                    .windows(2)
+                    .filter_map(|x| {
+                        let column = Rect::from_min_max(Pos2::new(x[0], area.min.y), Pos2::new(x[1], area.max.y));
+                        mapping.pixel_rect(column).map(|(x, _, w, _)| (x, w))
+                    })
+                    .collect();
+                let ys = cuts(&mut numbers, area.min.y, area.max.y);
+                let rows: Vec<(u32, u32)> = ys
+                    .windows(2)
+                    .filter_map(|y| {
+                        let row = Rect::from_min_max(Pos2::new(area.min.x, y[0]), Pos2::new(area.max.x, y[1]));
+                        mapping.pixel_rect(row).map(|(_, y, _, h)| (y, h))
+                    })
+                    .collect();
+
+                //pieces too thin for a pixel give none, every other one starts where the last ended
+                for (pieces, size) in [(columns, width), (rows, height)] {
+                    assert_eq!(pieces.first().map(|p| p.0), Some(0), "scale {} area {:?}", scale, area);
+                    for pair in pieces.windows(2) {
+                        assert_eq!(pair[0].0 + pair[0].1, pair[1].0, "scale {} area {:?}", scale, area);
+                    }
+                    assert_eq!(pieces.iter().map(|p| p.1).sum::<u32>(), size, "scale {} area {:?}", scale, area);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn monitors_with_their_own_scale_tile_the_desktop() {
+        let mut numbers = Numbers::new(2);
+        for desktop_scale in scales(&mut numbers) {
+            //side by side monitors in points, each one captured at its own scale
+            let mut left = numbers.range(-6000.0, 0.0).round();
+            let top = numbers.range(-1000.0, 1000.0).round();
+            let monitors: Vec<(Rect, f32)> = (0..3)
+                .map(|_| {
+                    let size = Vec2::new(numbers.range(600.0, 3000.0).round(), numbers.range(400.0, 2000.0).round());
+                    let monitor = Rect::from_min_size(Pos2::new(left, top), size);
+                    left += size.x;
+                    (monitor, numbers.range(0.5, 4.0))
+                })
+                .collect();
+            let desktop = monitors.iter().fold(monitors[0].0, |all, (m, _)| all.union(*m));
+            let mapping = ImageMapping::on_screen(desktop, desktop_scale);
+
+            let parts: Vec<(u32, u32, u32, u32)> = monitors.iter().map(|(m, _)| mapping.pixel_rect(*m).unwrap()).collect();
+            assert_eq!(parts[0].0, 0);
+            assert_eq!(parts[0].0 + parts[0].2, parts[1].0, "scale {}", desktop_scale);
+            assert_eq!(parts[1].0 + parts[1].2, parts[2].0, "scale {}", desktop_scale);
+            assert_eq!(parts[2].0 + parts[2].2, mapping.width, "scale {}", desktop_scale);
+
+            //the capture of a monitor maps its own points onto its own pixels
+            for (monitor, monitor_scale) in monitors {
+                let capture = ImageMapping::on_screen(monitor, monitor_scale);
+                let capture_scale = capture.scale();
+                assert!((capture_scale.x - monitor_scale).abs() < 0.01 && (capture_scale.y - monitor_scale).abs() < 0.01);
+                assert_eq!(capture.to_image(monitor.min), Pos2::ZERO);
+                let corner = capture.to_image(monitor.max) - Pos2::new(capture.width as f32, capture.height as f32);
+                assert!(corner.length() < 0.01, "{:?} at {} ends {:?} away", monitor, monitor_scale, corner);
+            }
+        }
+    }
+
+    #[test]
+    fn image_and_area_round_trip() {
+        let mut numbers = Numbers::new(3);
+        for scale in scales(&mut numbers) {
+            for area in areas(&mut numbers) {
+                let mapping = ImageMapping::on_screen(area, scale);
+                for _ in 0..20 {
+                    let p = Pos2::new(numbers.range(area.min.x, area.max.x), numbers.range(area.min.y, area.max.y));
+                    let back = mapping.to_area(mapping.to_image(p));
+                    //f32 keeps about 7 digits of coordinates up to 8000
+                    assert!((back - p).length() < 0.01, "{:?} at {} came back as {:?}", p, scale, back);
+                }
+                assert_eq!(mapping.to_area(Pos2::ZERO), area.min);
+            }
+        }
+    }
+
+    #[test]
+    fn sizes_fit_keeping_their_proportions() {
+        let mut numbers = Numbers::new(4);
+        for _ in 0..500 {
+            let size = Vec2::new(numbers.range(1.0, 8000.0), numbers.range(1.0, 5000.0));
+            let bounds = Vec2::new(numbers.range(1.0, 3000.0), numbers.range(1.0, 2000.0));
+            let fitted = fit(size, bounds);
+            assert!(fitted.x <= size.x && fitted.y <= size.y, "{:?} grew to {:?}", size, fitted);
+            assert!(fitted.x <= bounds.x + 0.01 && fitted.y <= bounds.y + 0.01, "{:?} does not fit {:?}", fitted, bounds);
+            assert!((fitted.x / fitted.y - size.x / size.y).abs() < 0.001 * size.x / size.y);
+            //one side touches the bounds unless the size already fitted
+            let touches = (fitted.x - bounds.x).abs() < 0.01 || (fitted.y - bounds.y).abs() < 0.01;
+            assert!(touches || fitted == size, "{:?} in {:?} gave {:?}", size, bounds, fitted);
+        }
+    }
+
+    #[test]
+    fn server_values_are_converted_like_display_info_does() {
+        let mut numbers = Numbers::new(5);
+        for scale in scales(&mut numbers) {
+            let (x, y) = (numbers.range(-8000.0, 8000.0) as i32, numbers.range(-4000.0, 4000.0) as i32);
+            let (width, height) = (numbers.range(1.0, 8000.0) as u32, numbers.range(1.0, 4000.0) as u32);
+            let rect = server_rect(x, y, width, height, scale);
+            assert_eq!(rect.min, Pos2::new(((x as f32) / scale) as i32 as f32, ((y as f32) / scale) as i32 as f32));
+            assert_eq!(rect.size(), Vec2::new(((width as f32) / scale) as u32 as f32, ((height as f32) / scale) as u32 as f32));
+            let pointer = server_pos(x, y, scale);
+            assert!((pointer.x * scale - x as f32).abs() < 0.01 && (pointer.y * scale - y as f32).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use image::{imageops, Pixel, Rgba, RgbaImage};
+
+//Arrow pointer drawn at 1x, the hotspot is the top-left pixel.
+//B is the outline, W the fill and . is transparent
+const ARROW: [&str; 19] = [
+    "B...........",
+    "BB..........",
+    "BWB.........",
+    "BWWB........",
+    "BWWWB.......",
+    "BWWWWB......",
+    "BWWWWWB.....",
+    "BWWWWWWB....",
+    "BWWWWWWWB...",
+    "BWWWWWWWWB..",
+    "BWWWWWWWWWB.",
+    "BWWWWWWBBBBB",
+    "BWWWBWWB....",
+    "BWWBBWWB....",
+    "BWB..BWWB...",
+    "BB...BWWB...",
+    "B.....BWWB..",
+    "......BWWB..",
+    ".......BB...",
+];
+
+//sizes of the ring at 1x
+const RING_RADIUS: f32 = 16.0;
+const RING_WIDTH: f32 = 3.0;
+const RING_COLOR: Rgba<u8> = Rgba([255, 200, 0, 170]);
+
+fn arrow_bitmap() -> RgbaImage {
+    RgbaImage::from_fn(ARROW[0].len() as u32, ARROW.len() as u32, |x, y| {
+        match ARROW[y as usize].as_bytes()[x as usize] {
+            b'B' => Rgba([0, 0, 0, 255]),
+            b'W' => Rgba([255, 255, 255, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        }
+    })
+}
+
+//Whether a mouse button is held down, to mark the clicks with the ring. Only X11
+//tells it, elsewhere no click is ever seen
+#[cfg(target_os = "linux")]
+pub fn button_down() -> bool {
+    use std::sync::Mutex;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, KeyButMask, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    //the connection and its root window are kept for every capture, it is opened
+    //again only after an error
+    static CONNECTION: Mutex<Option<(RustConnection, Window)>> = Mutex::new(None);
+
+    let mut connection = CONNECTION.lock().unwrap_or_else(|e| e.into_inner());
+    if connection.is_none() {
+        *connection = x11rb::connect(None).ok().map(|(conn, screen_num)| {
+            let root = conn.setup().roots[screen_num].root;
+            (conn, root)
+        });
+    }
+    let mask = connection.as_ref().and_then(|(conn, root)| {
+        let reply = conn.query_pointer(*root).ok()?.reply().ok()?;
+        Some(reply.mask)
+    });
+    match mask {
+        Some(mask) => {
+            mask.contains(KeyButMask::BUTTON1) || mask.contains(KeyButMask::BUTTON2) || mask.contains(KeyButMask::BUTTON3)
+        }
+        None => {
+            *connection = None;
+            false
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn button_down() -> bool {
+    false
+}
+
+//Draws the pointer with its hotspot at `hotspot` (pixels of the image), `scale` is
+//the size of a logical pixel on the captured screen. The ring, when asked for a
+//click, is drawn around the hotspot under the pointer
+pub fn composite(image: &mut RgbaImage, hotspot: (f32, f32), scale: f32, ring: bool) {
+    let scale = scale.max(1.0);
+    if ring {
+        draw_ring(image, hotspot, RING_RADIUS * scale, RING_WIDTH * scale);
+    }
+    let arrow = arrow_bitmap();
+    let arrow = imageops::resize(
+        &arrow,
+        (arrow.width() as f32 * scale).round() as u32,
+        (arrow.height() as f32 * scale).round() as u32,
+        imageops::FilterType::Nearest,
+    );
+    imageops::overlay(image, &arrow, hotspot.0.round() as i64, hotspot.1.round() as i64);
+}
+
+fn draw_ring(image: &mut RgbaImage, center: (f32, f32), radius: f32, width: f32) {
+    let outer = radius + width / 2.0;
+    let inner = radius - width / 2.0;
+    let min_x = (center.0 - outer).floor().max(0.0) as u32;
+    let min_y = (center.1 - outer).floor().max(0.0) as u32;
+    let max_x = ((center.0 + outer).ceil().max(0.0) as u32).min(image.width());
+    let max_y = ((center.1 + outer).ceil().max(0.0) as u32).min(image.height());
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let distance = ((x as f32 + 0.5 - center.0).powi(2) + (y as f32 + 0.5 - center.1).powi(2)).sqrt();
+            if distance >= inner && distance <= outer {
+                image.get_pixel_mut(x, y).blend(&RING_COLOR);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+    fn background(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, GRAY)
+    }
+
+    fn changed(image: &RgbaImage) -> Vec<(u32, u32)> {
+        image.enumerate_pixels().filter(|(_, _, p)| **p != GRAY).map(|(x, y, _)| (x, y)).collect()
+    }
+
+    #[test]
+    fn arrow_starts_at_the_hotspot() {
+        let mut image = background(40, 40);
+        composite(&mut image, (10.0, 5.0), 1.0, false);
+
+        assert_eq!(*image.get_pixel(10, 5), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(11, 7), Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(9, 5), GRAY);
+        assert_eq!(*image.get_pixel(11, 5), GRAY);
+        //the arrow is 12×19 and nothing outside of it is touched
+        assert!(changed(&image).iter().all(|(x, y)| (10..22).contains(x) && (5..24).contains(y)));
+    }
+
+    #[test]
+    fn arrow_follows_the_scale() {
+        let mut image = background(60, 60);
+        composite(&mut image, (0.0, 0.0), 2.0, false);
+        //every pixel of the bitmap becomes a 2×2 block
+        assert_eq!(*image.get_pixel(3, 5), Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(1, 1), Rgba([0, 0, 0, 255]));
+        assert!(changed(&image).iter().all(|(x, y)| *x < 24 && *y < 38));
+        assert!(changed(&image).iter().any(|(x, y)| *x >= 12 && *y >= 19));
+
+        //below 1 the pointer keeps its 1x size
+        let mut small = background(40, 40);
+        let mut unscaled = background(40, 40);
+        composite(&mut small, (3.0, 3.0), 0.5, false);
+        composite(&mut unscaled, (3.0, 3.0), 1.0, false);
+        assert_eq!(small, unscaled);
+    }
+
+    #[test]
+    fn ring_surrounds_the_hotspot() {
+        let mut image = background(80, 80);
+        draw_ring(&mut image, (40.0, 40.0), RING_RADIUS, RING_WIDTH);
+
+        assert_eq!(*image.get_pixel(40, 40), GRAY);
+        for (x, y) in [(40 + RING_RADIUS as u32, 40), (40, 40 - RING_RADIUS as u32), (39 - RING_RADIUS as u32, 40)] {
+            assert_ne!(*image.get_pixel(x, y), GRAY, "{},{} is outside the ring", x, y);
+        }
+        let outer = RING_RADIUS + RING_WIDTH / 2.0;
+        for (x, y) in changed(&image) {
+            let distance = ((x as f32 + 0.5 - 40.0).powi(2) + (y as f32 + 0.5 - 40.0).powi(2)).sqrt();
+            assert!(distance <= outer, "{},{} is too far", x, y);
+        }
+
+        //the ring is drawn under the pointer
+        let mut image = background(80, 80);
+        composite(&mut image, (40.0, 40.0), 1.0, true);
+        assert_eq!(*image.get_pixel(40, 40), Rgba([0, 0, 0, 255]));
+        assert_ne!(*image.get_pixel(40, 40 - RING_RADIUS as u32), GRAY);
+    }
+
+    #[test]
+    fn drawing_is_clipped_at_the_edges() {
+        //hotspot near the bottom-right corner, only the visible part is drawn
+        let mut image = background(20, 20);
+        composite(&mut image, (18.0, 18.0), 1.0, true);
+        assert_eq!(*image.get_pixel(18, 18), Rgba([0, 0, 0, 255]));
+        assert!(changed(&image).iter().any(|(x, y)| *x < 5 || *y < 5));
+
+        //hotspot left of and above the image, the ring still shows its part inside
+        let mut image = background(20, 20);
+        composite(&mut image, (-10.0, -4.0), 1.0, true);
+        assert!(!changed(&image).is_empty());
+
+        //far away nothing changes
+        let mut image = background(20, 20);
+        composite(&mut image, (-200.0, 500.0), 3.0, true);
+        draw_ring(&mut image, (1e6, -1e6), RING_RADIUS, RING_WIDTH);
+        assert!(changed(&image).is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+use crate::naming::{self, NamingContext};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ImageFormat {
+    Jpg,
+    Png,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Option<ImageFormat> {
+        match ext.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(ImageFormat::Jpg),
+            "png" => Some(ImageFormat::Png),
+            "gif" => Some(ImageFormat::Gif),
+            _ => None,
+        }
+    }
+
+    fn encoder_format(&self) -> image::ImageFormat {
+        match self {
+            ImageFormat::Jpg => image::ImageFormat::Jpeg,
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Gif => image::ImageFormat::Gif,
+        }
+    }
+}
+
+pub enum Destination {
+    //a new file inside the directory, named after the template
+    Directory {
+        dir: PathBuf,
+        template: String,
+        naming: NamingContext,
+    },
+    //a file chosen by the user, the extension decides the format when it is a known one
+    File(PathBuf),
+}
+
+pub struct ExportOptions {
+    pub format: ImageFormat,
+}
+
+#[derive(Debug)]
+pub struct ExportedImage {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Encoding(image::ImageError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "{}", e),
+            ExportError::Encoding(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for ExportError {
+    fn from(e: image::ImageError) -> Self {
+        ExportError::Encoding(e)
+    }
+}
+
+fn resolve_path(
+    destination: &Destination,
+    options: &ExportOptions,
+) -> Result<(PathBuf, ImageFormat), ExportError> {
+    match destination {
+        Destination::Directory {
+            dir,
+            template,
+            naming,
+        } => {
+            let path =
+                naming::build_file_path(dir, template, naming, options.format.extension())?;
+            Ok((path, options.format))
+        }
+        Destination::File(path) => {
+            let known = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(ImageFormat::from_extension);
+            match known {
+                Some(format) => Ok((path.clone(), format)),
+                None => {
+                    let mut name = path.as_os_str().to_os_string();
+                    name.push(".");
+                    name.push(options.format.extension());
+                    Ok((PathBuf::from(name), options.format))
+                }
+            }
+        }
+    }
+}
+
+pub fn export_image(
+    image: &RgbaImage,
+    destination: &Destination,
+    options: &ExportOptions,
+) -> Result<ExportedImage, ExportError> {
+    let (path, format) = resolve_path(destination, options)?;
+    create_parent(&path)?;
+
+    image.save_with_format(&path, format.encoder_format())?;
+
+    Ok(ExportedImage {
+        path,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+fn create_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a fresh directory for each test, so that they can run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("export_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample() -> RgbaImage {
+        RgbaImage::from_pixel(7, 5, image::Rgba([200, 40, 40, 255]))
+    }
+
+    fn written_format(path: &Path) -> image::ImageFormat {
+        image::io::Reader::open(path)
+            .unwrap()
+            .with_guessed_format()
+            .unwrap()
+            .format()
+            .unwrap()
+    }
+
+    fn to_directory(dir: &Path, template: &str) -> Destination {
+        Destination::Directory {
+            dir: dir.to_path_buf(),
+            template: template.to_string(),
+            naming: NamingContext::new(1, 0, "Rectangle", 7, 5),
+        }
+    }
+
+    #[test]
+    fn directory_adds_a_suffix_on_collision() {
+        let dir = temp_dir("collision");
+        let options = ExportOptions { format: ImageFormat::Png };
+        let destination = to_directory(&dir, "shot");
+
+        let first = export_image(&sample(), &destination, &options).unwrap();
+        let second = export_image(&sample(), &destination, &options).unwrap();
+        let third = export_image(&sample(), &destination, &options).unwrap();
+
+        assert_eq!(first.path, dir.join("shot.png"));
+        assert_eq!(second.path, dir.join("shot_1.png"));
+        assert_eq!(third.path, dir.join("shot_2.png"));
+        assert!(third.path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_creates_missing_subdirectories() {
+        let dir = temp_dir("subdirectories");
+        let options = ExportOptions { format: ImageFormat::Png };
+
+        let exported = export_image(&sample(), &to_directory(&dir, "a/b/shot"), &options).unwrap();
+
+        assert_eq!(exported.path, dir.join("a").join("b").join("shot.png"));
+        assert!(exported.path.exists());
+        assert_eq!(written_format(&exported.path), image::ImageFormat::Png);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_extension_picks_the_format() {
+        let dir = temp_dir("extension");
+        let options = ExportOptions { format: ImageFormat::Png };
+
+        let exported = export_image(&sample(), &Destination::File(dir.join("b.JPEG")), &options).unwrap();
+
+        assert_eq!(exported.path, dir.join("b.JPEG"));
+        assert_eq!(written_format(&exported.path), image::ImageFormat::Jpeg);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_without_known_extension_gets_one() {
+        let dir = temp_dir("no_extension");
+        let options = ExportOptions { format: ImageFormat::Jpg };
+
+        let bare = export_image(&sample(), &Destination::File(dir.join("c")), &options).unwrap();
+        let unknown = export_image(&sample(), &Destination::File(dir.join("d.txt")), &options).unwrap();
+
+        assert_eq!(bare.path, dir.join("c.jpg"));
+        assert_eq!(unknown.path, dir.join("d.txt.jpg"));
+        assert_eq!(written_format(&bare.path), image::ImageFormat::Jpeg);
+        assert_eq!(written_format(&unknown.path), image::ImageFormat::Jpeg);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exported_image_reports_its_size() {
+        let dir = temp_dir("size");
+        let options = ExportOptions { format: ImageFormat::Png };
+
+        let exported = export_image(&sample(), &Destination::File(dir.join("e.png")), &options).unwrap();
+
+        assert_eq!((exported.width, exported.height), (7, 5));
+        assert_eq!(image::image_dimensions(&exported.path).unwrap(), (7, 5));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,37 +1,55 @@
 pub mod first_window {
 
-    use std::time::Duration;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
 
-    use crate::{hotkeys::CustomizeHotkey, FirstWindow, ModeOptions};
-    use egui::{ColorImage, ImageData, Response};
+    use crate::coords::{self, ImageMapping};
+    use crate::cursor;
+    use crate::export::{self, Destination, ExportError, ExportOptions, ExportedImage, ImageFormat};
+    use crate::history::HistoryEntry;
+    use crate::interval::IntervalSession;
+    use crate::naming;
+    use crate::recording::{self, Recording, RecordingError};
+    use crate::loupe;
+    use crate::monitors::{self, Monitor};
+    use crate::naming::NamingContext;
+    use crate::selection::{Preset, Selection};
+    use crate::post_processing::HistoryStep;
+    use crate::stitching;
+    use crate::transform::Geometry;
+    use crate::window_capture::{self, WindowInfo};
+    use crate::style;
+    use crate::{hotkeys::CustomizeHotkey, FirstWindow, LoadingState, ModeOptions, APP_TITLE};
+    use egui::{emath, Color32, ColorImage, ImageData, Pos2, Rect, Stroke, TextureHandle, Vec2};
 
 
+    use arboard::Clipboard;
     use global_hotkey::HotKeyState;
-    use image::{DynamicImage, EncodableLayout, ImageBuffer};
+    use image::{EncodableLayout, RgbaImage};
+    use mouse_position::mouse_position::Mouse;
+    use rfd::FileDialog;
     use rusttype::Font;
     use screenshots::Screen;
 
+    pub const SCROLL_PANEL_SIZE: Vec2 = Vec2::new(380.0, 140.0);
+    pub const INTERVAL_PANEL_SIZE: Vec2 = Vec2::new(380.0, 150.0);
+    pub const RECORDING_PANEL_SIZE: Vec2 = Vec2::new(300.0, 110.0);
+    pub const RECORDING_INDICATOR_SIZE: Vec2 = Vec2::new(260.0, 56.0);
+
     impl FirstWindow {
         pub fn set_width_height(&mut self) {
             match self.selected_mode {
-                ModeOptions::Rectangle => {
+                ModeOptions::Rectangle | ModeOptions::Scrolling | ModeOptions::Window => {
                     self.width = (self.rect_pos_f[0]) - (self.rect_pos[0]);
                     self.height = (self.rect_pos_f[1]) - self.rect_pos[1];
                 }
-                ModeOptions::FullScreen => {
+                ModeOptions::FullScreen | ModeOptions::AllScreens => {
                     self.width = self.image_texture.clone().unwrap().size[0] as f32
                         / self.multiplication_factor.unwrap();
                     self.height = self.image_texture.clone().unwrap().size[1] as f32
                         / self.multiplication_factor.unwrap();
                 }
             }
-            if self.current_os == "windows" {
-                
-                self.width = self.width * self.multiplication_factor.unwrap();
-                self.height = self.height * self.multiplication_factor.unwrap();
-                self.rect_pos[0] = self.rect_pos[0] * self.multiplication_factor.unwrap();
-                self.rect_pos[1] = self.rect_pos[1] * self.multiplication_factor.unwrap();
-            }
         }
 
         pub fn set_image_texture(&mut self) {
@@ -41,6 +59,10 @@ pub mod first_window {
                     self.screenshots_taken.clone().unwrap().height() as _,
                 ];
                 self.image_buffer = Some(self.screenshots_taken.clone().unwrap());
+                self.crop_area = None;
+                self.transform_undo.clear();
+                self.transform_redo.clear();
+                self.painting.reset_zoom();
 
                 let pixels = self
                     .screenshots_taken
@@ -57,42 +79,43 @@ pub mod first_window {
 
         pub fn take_screenshot(&mut self) {
             let screens = Screen::all().unwrap();
+            let selection = Rect::from_min_max(self.rect_pos, self.rect_pos_f);
             match self.selected_mode {
-                ModeOptions::Rectangle => {
+                ModeOptions::Rectangle | ModeOptions::Window => {
+                    //the overlay covers the whole virtual desktop, so the selection can cross
+                    //several monitors
+                    let area = selection.translate(self.overlay_bounds().min.to_vec2());
+                    let (image, pointer) = match self.frozen_frame.take() {
+                        Some(frozen) => (self.crop_frozen_frame(&frozen, selection), self.frozen_pointer),
+                        None => (Self::capture_virtual_area(&screens, area), self.pointer_to_capture()),
+                    };
+                    self.frozen_texture = None;
+                    if let Some(image) = image {
+                        self.screenshots_taken = Some(self.with_cursor(image, area, pointer));
+                        self.last_region = Some(area);
+                    }
+                    self.set_width_height();
+                    self.set_image_texture();
+                }
+                ModeOptions::Scrolling => {
+                    //the overlay window covers exactly the selected monitor,
+                    //so the rectangle is already relative to its top-left corner
                     self.set_width_height();
 
-                    for screen in screens {
-                        if self.screen_to_show.is_none() == false
-                            && screen.display_info.id == self.screen_to_show.unwrap()
-                        {
-                            if screen.display_info.is_primary == false {
-                                self.rect_pos.x -= screen.display_info.width as f32;
-                            }
+                    let image = self.capture_screen_area(selection);
 
-                            if self.rect_pos[0] < 0.0 {
-                                self.rect_pos[0] += self.screen_size.unwrap()[0];
-                            }
-                            let image = screen.capture_area(
-                                self.rect_pos[0] as i32,
-                                self.rect_pos[1] as i32,
-                                self.width as u32,
-                                self.height as u32,
-                            );
-
-                            if image.is_err() == false {
-                                self.screenshots_taken = Some(image.unwrap());
-                            } else {
-                            }
-                        }
-                    }
-                    self.set_image_texture();
+                    self.scroll_panel_pos = self.free_corner(selection, SCROLL_PANEL_SIZE);
+                    self.scroll_area = Some(selection);
+                    self.scroll_frames = image.into_iter().collect();
                 }
                 ModeOptions::FullScreen => {
+                    let pointer = self.pointer_to_capture();
                     for screen in screens {
                         if screen.display_info.id == self.screen_to_show.unwrap() {
                             let image = screen.capture();
                             if image.is_err() == false {
-                                self.screenshots_taken = Some(image.unwrap());
+                                let area = Rect::from_min_size(self.frame_initial_pos.unwrap(), self.screen_size.unwrap());
+                                self.screenshots_taken = Some(self.with_cursor(image.unwrap(), area, pointer));
                             } else {
                             }
                         }
@@ -100,34 +123,576 @@ pub mod first_window {
                     self.set_image_texture();
                     self.set_width_height();
                 }
+                ModeOptions::AllScreens => {
+                    let pointer = self.pointer_to_capture();
+                    if let (Some(desktop), Some(area)) = (Self::capture_virtual_desktop(&screens), self.captured_area()) {
+                        self.screenshots_taken = Some(self.with_cursor(desktop, area, pointer));
+                    }
+                    self.set_image_texture();
+                    self.set_width_height();
+                }
+            }
+        }
+
+        pub fn capture_virtual_desktop(screens: &[Screen]) -> Option<RgbaImage> {
+            let captures: Vec<(Monitor, RgbaImage)> = screens
+                .iter()
+                .filter_map(|screen| {
+                    let image = screen.capture().ok()?;
+                    Some((Monitor::from_display_info(&screen.display_info), image))
+                })
+                .collect();
+            monitors::compose_virtual_desktop(&captures)
+        }
+
+        //Captures an area of the virtual desktop: every monitor crossed by it
+        //gives its own part of the image
+        pub fn capture_virtual_area(screens: &[Screen], area: Rect) -> Option<RgbaImage> {
+            let all: Vec<Monitor> = screens
+                .iter()
+                .map(|s| Monitor::from_display_info(&s.display_info))
+                .collect();
+            let pieces: Vec<(Rect, f32, RgbaImage)> = monitors::split_by_monitor(&all, area)
+                .into_iter()
+                .filter_map(|(monitor, part)| {
+                    let screen = screens.iter().find(|s| s.display_info.id == monitor.id)?;
+                    let part = part.translate(monitor.rect().min.to_vec2());
+                    let (scale, image) = Self::capture_monitor_part(screen, &monitor, part)?;
+                    Some((part, scale, image))
+                })
+                .collect();
+            monitors::compose_area(area, &pieces)
+        }
+
+        //Grabs `part` (virtual desktop points) of a monitor with its pixels per point.
+        //The whole monitor is grabbed and then cropped because capture_area takes whole
+        //points, which can not address every pixel with a fractional scale factor
+        fn capture_monitor_part(screen: &Screen, monitor: &Monitor, part: Rect) -> Option<(f32, RgbaImage)> {
+            let capture = screen.capture().ok()?;
+            let mapping = ImageMapping::new(monitor.rect(), capture.width(), capture.height());
+            let (x, y, width, height) = mapping.pixel_rect(part)?;
+            Some((
+                mapping.scale().x,
+                image::imageops::crop_imm(&capture, x, y, width, height).to_image(),
+            ))
+        }
+
+        //Grabs in the background the screen under the overlay before it shows up, gives
+        //true once it is done. It is what the loupe shows and, when the screen is frozen,
+        //the overlay backdrop the selection is cropped from
+        pub fn check_backdrop_grab(&mut self) -> bool {
+            let job = match self.backdrop_job.take() {
+                Some(job) => job,
+                None => {
+                    let area = self.overlay_bounds();
+                    self.frozen_pointer = self.pointer_to_capture();
+                    self.backdrop_job = Some(std::thread::spawn(move || {
+                        Self::capture_virtual_area(&Screen::all().unwrap_or_default(), area)
+                    }));
+                    return false;
+                }
+            };
+            if !job.is_finished() {
+                self.backdrop_job = Some(job);
+                return false;
+            }
+            let backdrop = job.join().ok().flatten();
+            if self.freeze_screen && self.selected_mode == ModeOptions::Rectangle {
+                self.frozen_frame = backdrop;
+                self.frozen_texture = None;
+            } else {
+                self.loupe_frame = backdrop;
+            }
+            self.backdrop_ready = true;
+            true
+        }
+
+        //Where the pointer is, in the units of the monitors, when it has to appear in the captures,
+        //and whether a button is down, only asked when clicks are highlighted
+        pub fn pointer_to_capture(&self) -> Option<(Pos2, bool)> {
+            if !self.capture_cursor {
+                return None;
+            }
+            let mult = self.multiplication_factor.unwrap_or(1.0);
+            match Mouse::get_mouse_position() {
+                Mouse::Position { x, y } => Some((
+                    coords::server_pos(x, y, mult),
+                    self.cursor_ring && cursor::button_down(),
+                )),
+                Mouse::Error => None,
+            }
+        }
+
+        //Draws the pointer on the capture of `area` when it is inside of it, with the ring
+        //when it was clicking. It has the size it has on the monitor it is on
+        pub fn with_cursor(&self, mut image: RgbaImage, area: Rect, pointer: Option<(Pos2, bool)>) -> RgbaImage {
+            if let Some((pointer, clicking)) = pointer.filter(|(p, _)| area.contains(*p)) {
+                let hotspot = ImageMapping::new(area, image.width(), image.height()).to_image(pointer);
+                let scale = monitors::monitor_at(&monitors::all_monitors(), pointer).map_or(1.0, |m| m.scale_factor);
+                cursor::composite(&mut image, (hotspot.x, hotspot.y), scale, clicking);
+            }
+            image
+        }
+
+        //The area of the virtual desktop grabbed by the last capture
+        pub fn captured_area(&self) -> Option<Rect> {
+            match self.selected_mode {
+                ModeOptions::Rectangle | ModeOptions::Window => self.last_region,
+                ModeOptions::FullScreen => Some(Rect::from_min_size(self.frame_initial_pos?, self.screen_size?)),
+                ModeOptions::AllScreens => {
+                    let (x, y, width, height) = monitors::virtual_bounds(&monitors::all_monitors())?;
+                    Some(Rect::from_min_size(
+                        Pos2::new(x as f32, y as f32),
+                        Vec2::new(width as f32, height as f32),
+                    ))
+                }
+                ModeOptions::Scrolling => None,
+            }
+        }
+
+        //the overlays with the loupe start from a grab of the screen under them
+        pub fn needs_backdrop(&self) -> bool {
+            matches!(self.selected_mode, ModeOptions::Rectangle | ModeOptions::Scrolling) && !self.backdrop_ready
+        }
+
+        pub fn frozen_backdrop(&mut self, ctx: &egui::Context) -> Option<egui::TextureId> {
+            let frozen = self.frozen_frame.as_ref()?;
+            let texture = self.frozen_texture.get_or_insert_with(|| {
+                let image = ColorImage::from_rgba_unmultiplied(
+                    [frozen.width() as usize, frozen.height() as usize],
+                    frozen.as_raw(),
+                );
+                ctx.load_texture("frozen_frame", ImageData::from(image), Default::default())
+            });
+            Some(texture.id())
+        }
+
+        //The frozen frame covers the whole overlay, the selection only has to be scaled
+        //to the pixels of the image
+        fn crop_frozen_frame(&self, frozen: &RgbaImage, selection: Rect) -> Option<RgbaImage> {
+            let overlay = Rect::from_min_size(Pos2::ZERO, self.overlay_bounds().size());
+            let (x, y, width, height) = ImageMapping::new(overlay, frozen.width(), frozen.height()).pixel_rect(selection)?;
+            Some(image::imageops::crop_imm(frozen, x, y, width, height).to_image())
+        }
+
+        //Captures `area`, given relative to the top-left corner of the selected monitor
+        pub fn capture_screen_area(&self, area: Rect) -> Option<RgbaImage> {
+            let screen = Screen::all()
+                .ok()?
+                .into_iter()
+                .find(|s| Some(s.display_info.id) == self.screen_to_show)?;
+            let monitor = Monitor::from_display_info(&screen.display_info);
+            Self::capture_monitor_part(&screen, &monitor, area.translate(monitor.rect().min.to_vec2()))
+                .map(|(_, image)| image)
+        }
+
+        pub fn add_scroll_frame(&mut self) {
+            if let Some(frame) = self.scroll_area.and_then(|a| self.capture_screen_area(a)) {
+                //a frame equal to the last one means the content has not been scrolled yet
+                if self.scroll_frames.last() != Some(&frame) {
+                    self.scroll_frames.push(frame);
+                }
+            }
+        }
+
+        pub fn finish_scrolling_capture(&mut self) {
+            let frames = std::mem::take(&mut self.scroll_frames);
+            self.scroll_area = None;
+            match stitching::stitch_vertical(&frames) {
+                Some(stitched) => {
+                    self.open_in_editor(stitched);
+                    self.fresh_capture = true;
+                }
+                None => self.selected_window = 1,
+            }
+        }
+
+        pub fn cancel_scrolling_capture(&mut self) {
+            self.scroll_frames = Vec::new();
+            self.scroll_area = None;
+            self.selected_window = 1;
+        }
+
+        //Position for a panel in a corner of the monitor not covered by the selection
+        pub fn free_corner(&self, selection: Rect, panel: Vec2) -> Pos2 {
+            let screen = self.screen_size.unwrap();
+            let selection = selection.expand(10.0);
+            let corners = [
+                Pos2::new(screen.x - panel.x - 20.0, screen.y - panel.y - 60.0),
+                Pos2::new(20.0, screen.y - panel.y - 60.0),
+                Pos2::new(screen.x - panel.x - 20.0, 20.0),
+                Pos2::new(20.0, 20.0),
+            ];
+            let corner = corners
+                .iter()
+                .find(|c| !Rect::from_min_size(**c, panel).intersects(selection))
+                .unwrap_or(&corners[0]);
+            self.frame_initial_pos.unwrap() + corner.to_vec2()
+        }
+
+        //Applies the pointer and keyboard input of this frame to the selection, gives true
+        //when the capture has been confirmed with Enter or a double-click inside the rectangle
+        pub fn update_selection(&mut self, ui: &egui::Ui, bounds: Rect) -> bool {
+            let confirmed = self.selection.handle_input(ui, bounds);
+            match self.selection.get_rect() {
+                Some(rect) if confirmed => {
+                    self.rect_pos = rect.min;
+                    self.rect_pos_f = rect.max;
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        //Selects the region typed in the home window, given in physical pixels of the
+        //virtual desktop, and captures it without showing the overlay
+        pub fn select_exact_region(&mut self) {
+            let overlay = self.overlay_bounds();
+            let mapping = self.overlay_mapping(overlay);
+            let origin = overlay.min.to_vec2() * mapping.scale();
+            let (x, y, width, height) = self.exact_region;
+            let min = Pos2::new(x as f32, y as f32) - origin;
+            let rect = Rect::from_min_max(
+                mapping.to_area(min),
+                mapping.to_area(min + Vec2::new(width as f32, height as f32)),
+            );
+            self.rect_pos = rect.min;
+            self.rect_pos_f = rect.max;
+            self.selected_window = 3;
+        }
+
+        pub fn refresh_window_list(&mut self) {
+            self.window_list_loaded = true;
+            match window_capture::list_windows(APP_TITLE) {
+                Ok(windows) => {
+                    if !windows.iter().any(|w| Some(w.id) == self.picked_app_window) {
+                        self.picked_app_window = None;
+                    }
+                    self.window_list = windows;
+                }
+                Err(e) => {
+                    self.window_list = Vec::new();
+                    self.picked_app_window = None;
+                    self.toasts.as_mut().unwrap().error(format!("Can not list the windows: {}", e)).set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                }
+            }
+        }
+
+        //Selects the bounds of an application window and captures them
+        //without going through the overlay
+        pub fn select_app_window(&mut self, window: &WindowInfo) {
+            let rect = window
+                .logical_rect(self.multiplication_factor.unwrap_or(1.0))
+                .translate(-self.overlay_bounds().min.to_vec2());
+            self.rect_pos = rect.min;
+            self.rect_pos_f = rect.max;
+            self.selected_window = 3;
+        }
+
+        //Captures again what the last capture has grabbed, used by the interval capture
+        pub fn capture_again(&self) -> Option<RgbaImage> {
+            let screens = Screen::all().unwrap_or_default();
+            let pointer = self.pointer_to_capture();
+            let image = match self.selected_mode {
+                ModeOptions::Rectangle | ModeOptions::Window => self
+                    .last_region
+                    .and_then(|area| Self::capture_virtual_area(&screens, area)),
+                ModeOptions::FullScreen => screens
+                    .iter()
+                    .find(|s| Some(s.display_info.id) == self.screen_to_show)?
+                    .capture()
+                    .ok(),
+                ModeOptions::AllScreens => Self::capture_virtual_desktop(&screens),
+                ModeOptions::Scrolling => None,
+            }?;
+            Some(self.with_cursor(image, self.captured_area()?, pointer))
+        }
+
+        //Starts saving a frame every few seconds from the capture just taken
+        pub fn start_interval_capture(&mut self) {
+            self.interval_session = Some(IntervalSession::new(self.interval_settings));
+            self.interval_panel_pos = self.free_corner(
+                Rect::from_min_max(self.rect_pos, self.rect_pos_f),
+                INTERVAL_PANEL_SIZE,
+            );
+            if let Some(first) = self.image_buffer.clone() {
+                self.save_interval_frame(first);
+            }
+            self.selected_window = 12;
+        }
+
+        pub fn save_interval_frame(&mut self, frame: RgbaImage) {
+            let accepted = match self.interval_session.as_mut() {
+                Some(session) => session.accept(&frame),
+                None => return,
+            };
+            if !accepted {
+                return;
+            }
+            match self.export_to_default_folder(&frame) {
+                Ok(exported) => {
+                    let _ = self.history.add(HistoryEntry::new(exported.path, exported.width, exported.height));
+                }
+                Err(e) => {
+                    self.toasts.as_mut().unwrap().error(format!("Interval capture stopped: {}", e)).set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                    self.stop_interval_capture();
+                }
+            }
+        }
+
+        pub fn stop_interval_capture(&mut self) {
+            if let Some(session) = self.interval_session.take() {
+                self.toasts.as_mut().unwrap().success(format!("Interval capture: {} frames saved, {} identical skipped", session.saved, session.skipped)).set_duration(Some(Duration::from_secs(5)));
+                self.show_toast = true;
+            }
+            self.loading_state = LoadingState::NotLoaded;
+            self.image = None;
+            self.selected_window = 1;
+        }
+
+        //Starts recording the region of the capture just taken, which is the first frame.
+        //The panel is shown only when it stays out of the recorded area, on another
+        //monitor for a full screen recording. Otherwise a small indicator with the
+        //hotkeys is kept in a corner and the pixels under it are left out of the frames
+        pub fn start_recording(&mut self) {
+            let monitor = Rect::from_min_size(self.frame_initial_pos.unwrap(), self.screen_size.unwrap());
+            self.recording_panel_pos = match (&self.selected_mode, self.last_region) {
+                (ModeOptions::Rectangle | ModeOptions::Window, Some(area)) => {
+                    let pos = self.free_corner(area.translate(-monitor.min.to_vec2()), RECORDING_PANEL_SIZE);
+                    if Rect::from_min_size(pos, RECORDING_PANEL_SIZE).intersects(area) {
+                        None
+                    } else {
+                        Some(pos)
+                    }
+                }
+                (ModeOptions::FullScreen, _) => monitors::all_monitors()
+                    .iter()
+                    .find(|m| Some(m.id) != self.screen_to_show)
+                    .map(|m| m.rect().min + Vec2::splat(20.0)),
+                _ => None,
+            };
+            let whole = Rect::from_min_size(Pos2::ZERO, monitor.size());
+            let recorded = match (&self.selected_mode, self.last_region) {
+                (ModeOptions::Rectangle | ModeOptions::Window, Some(area)) => area.translate(-monitor.min.to_vec2()),
+                _ => whole,
+            };
+            self.recording_indicator_pos = self.free_corner(recorded, RECORDING_INDICATOR_SIZE);
+
+            let mut recording = Recording::new(self.recording_settings);
+            if let Some(first) = self.image_buffer.clone() {
+                if self.recording_panel_pos.is_none() {
+                    let mapping = ImageMapping::new(recorded.translate(monitor.min.to_vec2()), first.width(), first.height());
+                    if let Some(area) = mapping.pixel_rect(Rect::from_min_size(self.recording_indicator_pos, RECORDING_INDICATOR_SIZE)) {
+                        recording.hide_area(area);
+                    }
+                }
+                recording.push(first);
+            }
+            self.recording = Some(recording);
+            self.loading_state = LoadingState::NotLoaded;
+            self.image = None;
+            self.selected_window = 13;
+        }
+
+        pub fn record_frame(&mut self) {
+            if let Some(frame) = self.capture_again() {
+                if let Some(recording) = self.recording.as_mut() {
+                    recording.push(frame);
+                }
+            }
+        }
+
+        //Encodes the recorded frames in the background, the result is
+        //picked up by the home window
+        pub fn stop_recording(&mut self) {
+            self.selected_window = 1;
+            let recording = match self.recording.take() {
+                Some(r) => r,
+                None => return,
+            };
+            let settings = recording.get_settings();
+            let frames = recording.into_frames();
+            let (width, height) = frames.first().map_or((0, 0), |f| f.dimensions());
+
+            self.capture_counter += 1;
+            let context = NamingContext::new(self.capture_counter, self.monitor_index(), self.mode_tag(), width, height);
+            let dir = self.filepath.clone().unwrap_or(PathBuf::from("./screenshot"));
+            let path = match naming::build_file_path(&dir, &self.naming_template, &context, settings.format.extension()) {
+                Ok(p) => p,
+                Err(e) => {
+                    self.toasts.as_mut().unwrap().error(format!("Unable to save the recording: {}", e)).set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                    return;
+                }
+            };
+            self.toasts.as_mut().unwrap().info(format!("Encoding {} frames...", frames.len())).set_duration(Some(Duration::from_secs(3)));
+            self.show_toast = true;
+            self.recording_job = Some(std::thread::spawn(move || {
+                recording::encode(&frames, settings.fps, settings.format, &path)?;
+                Ok(ExportedImage { path, width, height })
+            }));
+        }
+
+        pub fn cancel_recording(&mut self) {
+            self.recording = None;
+            self.selected_window = 1;
+        }
+
+        pub fn check_recording_job(&mut self) {
+            if !self.recording_job.as_ref().is_some_and(|job| job.is_finished()) {
+                return;
+            }
+            let result: Result<ExportedImage, RecordingError> = match self.recording_job.take().unwrap().join() {
+                Ok(result) => result,
+                Err(_) => {
+                    self.toasts.as_mut().unwrap().error("Unable to save the recording: the encoder stopped unexpectedly").set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                    return;
+                }
+            };
+            match result {
+                Ok(exported) => {
+                    let _ = self.history.add(HistoryEntry::new(exported.path.clone(), exported.width, exported.height));
+                    self.toasts.as_mut().unwrap().success(format!("Recording saved in {}", exported.path.display())).set_duration(Some(Duration::from_secs(5)));
+                }
+                Err(e) => {
+                    self.toasts.as_mut().unwrap().error(format!("Unable to save the recording: {}", e)).set_duration(Some(Duration::from_secs(5)));
+                }
+            }
+            self.show_toast = true;
+        }
+
+        //Goes to the selection, after the countdown when a timer is set
+        pub fn start_capture(&mut self) {
+            if self.timer_seconds > 0 {
+                self.countdown_end = Some(Instant::now() + Duration::from_secs(self.timer_seconds));
+                self.selected_window = 11;
+            } else {
+                self.selected_window = 2;
+            }
+        }
+
+        //None once the countdown is over
+        pub fn countdown_remaining(&self) -> Option<Duration> {
+            self.countdown_end
+                .and_then(|end| end.checked_duration_since(Instant::now()))
+                .filter(|remaining| !remaining.is_zero())
+        }
+
+        //Selects again the area of the last rectangle capture, gives false when there is
+        //none or when no monitor shows that area anymore
+        pub fn select_last_region(&mut self) -> bool {
+            let area = match self.last_region {
+                Some(area) => area,
+                None => {
+                    self.toasts.as_mut().unwrap().info("No region captured yet").set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                    return false;
+                }
+            };
+            if monitors::split_by_monitor(&monitors::all_monitors(), area).is_empty() {
+                self.toasts.as_mut().unwrap().error("The last region is not on any monitor anymore").set_duration(Some(Duration::from_secs(5)));
+                self.show_toast = true;
+                return false;
+            }
+            //the region is remembered in virtual desktop coordinates, the capture
+            //works on rectangle selections: the mode of the user comes back at home
+            if self.mode_before_last_region.is_none() {
+                let mode = std::mem::replace(&mut self.selected_mode, ModeOptions::Rectangle);
+                let label = std::mem::replace(&mut self.selected_mode_string, "Rectangle".to_string());
+                self.mode_before_last_region = Some((mode, label));
             }
+            let rect = area.translate(-self.overlay_bounds().min.to_vec2());
+            self.rect_pos = rect.min;
+            self.rect_pos_f = rect.max;
+            self.selected_window = 3;
+            true
         }
 
-        pub fn define_rectangle(&mut self) {
-            if self.mouse_pos.is_none() == false && self.mouse_pos_f.is_none() == false {
-                let diff_x = self.mouse_pos_f.unwrap()[0] - self.mouse_pos.unwrap()[0];
-                let diff_y = self.mouse_pos_f.unwrap()[1] - self.mouse_pos.unwrap()[1];
+        pub fn restore_mode(&mut self) {
+            if let Some((mode, label)) = self.mode_before_last_region.take() {
+                self.selected_mode = mode;
+                self.selected_mode_string = label;
+            }
+        }
 
-                if diff_x > 0.0 && diff_y > 0.0 {
-                    self.rect_pos = self.mouse_pos.unwrap();
-                    self.rect_pos_f = self.mouse_pos_f.unwrap();
-                } else if diff_x < 0.0 && diff_y < 0.0 {
-                    self.rect_pos = self.mouse_pos_f.unwrap();
-                    self.rect_pos_f = self.mouse_pos.unwrap();
-                } else if diff_x < 0.0 && diff_y > 0.0 {
-                    self.rect_pos[0] = self.mouse_pos_f.unwrap()[0];
-                    self.rect_pos[1] = self.mouse_pos.unwrap()[1];
-                    self.rect_pos_f[0] = self.mouse_pos.unwrap()[0];
-                    self.rect_pos_f[1] = self.mouse_pos_f.unwrap()[1];
-                } else if diff_x > 0.0 && diff_y < 0.0 {
-                    self.rect_pos[0] = self.mouse_pos.unwrap()[0];
-                    self.rect_pos[1] = self.mouse_pos_f.unwrap()[1];
-                    self.rect_pos_f[0] = self.mouse_pos_f.unwrap()[0];
-                    self.rect_pos_f[1] = self.mouse_pos.unwrap()[1];
+        //The last region hotkey from the editor or the history: the image in the editor
+        //is replaced by the new capture, as when a history entry is opened
+        pub fn repeat_last_region(&mut self) -> bool {
+            if !self.select_last_region() {
+                return false;
+            }
+            self.circle_pixels = Vec::new();
+            self.square_pixels = Vec::new();
+            self.arrow_pixels = Vec::new();
+            self.text_pixels = Vec::new();
+            self.line_pixels = Vec::new();
+            self.save = true;
+            self.cut_clicked = false;
+            self.loading_state = LoadingState::NotLoaded;
+            self.image = None;
+            true
+        }
+
+        //Size and position of the selection in physical pixels above it, and the loupe
+        //with the position and the color of the pixel under the cursor
+        pub fn paint_selection_info(&mut self, ui: &egui::Ui, overlay: Rect) {
+            let bounds = Rect::from_min_size(Pos2::ZERO, overlay.size());
+            let mapping = self.overlay_mapping(overlay);
+            let origin = overlay.min.to_vec2() * mapping.scale();
+            let physical = |p: Pos2| {
+                let p = mapping.to_image(p) + origin;
+                (p.x.round() as i64, p.y.round() as i64)
+            };
+
+            if let Some(rect) = self.selection.get_rect() {
+                let (x, y) = physical(rect.min);
+                let (_, _, width, height) = mapping.pixel_rect(rect).unwrap_or_default();
+                let text = format!("x {}  y {}  {} × {}", x, y, width, height);
+                let galley = ui.painter().layout_no_wrap(text, egui::FontId::monospace(14.0), Color32::WHITE);
+                //above the rectangle, inside it when there is no room on top
+                let pos = if rect.min.y > galley.size().y + 8.0 {
+                    rect.left_top() - Vec2::new(0.0, galley.size().y + 6.0)
+                } else {
+                    rect.left_top() + Vec2::new(4.0, 4.0)
+                };
+                ui.painter().rect_filled(
+                    Rect::from_min_size(pos, galley.size()).expand(2.0),
+                    2.0,
+                    Color32::from_black_alpha(200),
+                );
+                ui.painter().galley(pos, galley);
+            }
+
+            if let Some(pointer) = ui.input(|i| i.pointer.hover_pos()) {
+                if let Some(sample) = self.loupe_sample(pointer, overlay) {
+                    let (x, y) = physical(pointer);
+                    let text = format!("{}, {}  {}", x, y, loupe::hex_color(loupe::center_color(&sample)));
+                    loupe::paint(ui.painter(), loupe::loupe_rect(pointer, bounds), &sample, &text);
                 }
             }
         }
 
+        //Pixels around the cursor, from the screen as it was before the overlay showed up:
+        //the overlay tint must not change the colors
+        fn loupe_sample(&self, pointer: Pos2, overlay: Rect) -> Option<RgbaImage> {
+            let backdrop = self.frozen_frame.as_ref().or(self.loupe_frame.as_ref())?;
+            let center = self.overlay_mapping(overlay).to_image(pointer);
+            Some(loupe::sample(backdrop, (center.x.floor() as i64, center.y.floor() as i64)))
+        }
+
+        //Points of the overlay, from its top-left corner, to the pixels of the screen under
+        //it: the ones of the grabbed backdrop when there is one
+        fn overlay_mapping(&self, overlay: Rect) -> ImageMapping {
+            let area = Rect::from_min_size(Pos2::ZERO, overlay.size());
+            match self.frozen_frame.as_ref().or(self.loupe_frame.as_ref()) {
+                Some(backdrop) => ImageMapping::new(area, backdrop.width(), backdrop.height()),
+                None => ImageMapping::on_screen(area, self.multiplication_factor.unwrap_or(1.0)),
+            }
+        }
+
         pub fn load_image(&mut self, ui: &mut egui::Ui) {
             let img = ui.ctx().load_texture(
                 "image_texture",
@@ -137,207 +702,508 @@ pub mod first_window {
             self.image = Some(img);
         }
 
+        //Draws the annotations on the part of the image shown by the editor
         pub fn edit_image(&mut self, ui: &mut egui::Ui) {
-            if self.circle_pixels.is_empty() == false {
-                for c in self.circle_pixels.clone() {
-                    imageproc::drawing::draw_hollow_circle_mut(
-                        self.image_buffer.as_mut().unwrap(),
-                        (c.0.x as i32, c.0.y as i32),
-                        c.1 as i32,
-                        image::Rgba([c.2.r(), c.2.g(), c.2.b(), c.2.a()]),
-                    );
-                }
-            }
-
-            if self.square_pixels.is_empty() == false {
-   
-                for p in self.square_pixels.clone() {
-                    let w = p.0.width() as u32;
-                    let h = p.0.height() as u32;
-                    let rett =
-                        imageproc::rect::Rect::at(p.0.left_top().x as i32, p.0.left_top().y as i32)
-                            .of_size(w, h);
-                    imageproc::drawing::draw_hollow_rect_mut(
-                        self.image_buffer.as_mut().unwrap(),
-                        rett,
-                        image::Rgba([p.1.r(), p.1.g(), p.1.b(), p.1.a()]),
-                    );
-                }
-            }
-
-       
-            if self.arrow_pixels.is_empty() == false {
-                for p in self.arrow_pixels.clone() {
-                    let head = p.0[1];
-                    for pi in p.0 {
-                        imageproc::drawing::draw_line_segment_mut(
-                            self.image_buffer.as_mut().unwrap(),
-                            (pi.x, pi.y),
-                            (head.x, head.y),
-                            image::Rgba([p.1.r(), p.1.g(), p.1.b(), p.1.a()]),
-                        );
-                    }
-                }
+            let mut view = match self.view_image() {
+                Some(v) => v,
+                None => return,
+            };
+            for c in self.circle_pixels.iter() {
+                style::burn_circle(&mut view, c.0, c.1, &c.2);
+            }
+
+            for p in self.square_pixels.iter() {
+                style::burn_rect(&mut view, p.0, &p.1);
+            }
+
+            //the points of an arrow go by pairs: the shaft and the two sides of the head
+            for p in self.arrow_pixels.iter() {
+                let segments: Vec<(Pos2, Pos2)> = p
+                    .0
+                    .chunks(2)
+                    .filter(|pair| pair.len() == 2)
+                    .map(|pair| (pair[0], pair[1]))
+                    .collect();
+                style::burn_segments(&mut view, &segments, &p.1);
             }
 
             if self.text_pixels.is_empty() == false {
                 let font_data: &[u8] = include_bytes!("../DejaVuSansMono.ttf");
                 let font: Font<'static> = Font::try_from_bytes(font_data).unwrap();
-                for t in self.text_pixels.clone() {
-                    imageproc::drawing::draw_text_mut(
-                        self.image_buffer.as_mut().unwrap(),
-                        image::Rgba([t.1.r(), t.1.g(), t.1.b(), t.1.a()]),
-                        t.0.x as i32,
-                        t.0.y as i32,
-                        rusttype::Scale {
-                            x: 20.0 * self.mult_factor.unwrap().0,
-                            y: 20.0 * self.mult_factor.unwrap().1,
-                        },
-                        &font,
-                        &t.2,
-                    );
-                }
-            }
-
-            if self.line_pixels.is_empty() == false {
-                for p in self.line_pixels.clone() {
-                    if p.0.is_empty() == false {
-                        for j in 0..p.0.len() - 1 {
-                            let start = p.0[j];
-                            let end = p.0[j + 1];
-
-                            imageproc::drawing::draw_line_segment_mut(
-                                self.image_buffer.as_mut().unwrap(),
-                                (start.x, start.y),
-                                (end.x, end.y),
-                                image::Rgba([p.1.r(), p.1.g(), p.1.b(), p.1.a()]),
-                            );
-                        }
-                    }
+                for t in self.text_pixels.iter() {
+                    style::burn_text(&mut view, t.0, &t.2, &t.1, &font);
                 }
             }
 
-            let ci = ColorImage::from_rgba_unmultiplied(
-                [
-                    self.image_buffer.clone().unwrap().dimensions().0 as usize,
-                    self.image_buffer.clone().unwrap().dimensions().1 as usize,
-                ],
-                self.image_buffer.clone().unwrap().as_bytes(),
-            );
-            let new_img =
-                ui.ctx()
-                    .load_texture("new image", ImageData::from(ci.clone()), Default::default());
-            self.image = Some(new_img);
+            for p in self.line_pixels.iter() {
+                style::burn_polyline(&mut view, &p.0, &p.1);
+            }
+
+            let (x, y) = self.crop_area.map_or((0, 0), |area| (area.0, area.1));
+            if let Some(img) = self.image_buffer.as_mut() {
+                image::imageops::replace(img, &view, x as i64, y as i64);
+            }
+            self.refresh_view(ui.ctx());
             self.save = true;
         }
-        pub fn load_cutted_img(&mut self, ui: &mut egui::Ui, response: Option<Response>) {
-            let di = DynamicImage::ImageRgba8(self.image_buffer.clone().unwrap());
-            let w = f32::abs(self.to_cut_rect.unwrap().0.x - self.to_cut_rect.unwrap().1.x);
-            let h = f32::abs(self.to_cut_rect.unwrap().0.y - self.to_cut_rect.unwrap().1.y);
-            let cutted: DynamicImage;
-            if self.current_os == "windows" {
-                cutted = di.crop_imm(
-                    ((self.to_cut_rect.unwrap().0.x - response.clone().unwrap().rect.left_top().x)
-                        / self.shrink_fact.unwrap()) as u32,
-                    ((self.to_cut_rect.unwrap().0.y - response.clone().unwrap().rect.left_top().y)
-                        / self.shrink_fact.unwrap()) as u32,
-                    (w / self.shrink_fact.unwrap()) as u32,
-                    (h / self.shrink_fact.unwrap()) as u32,
-                );
-            } else {
-                cutted = di.crop_imm(
-                    (((self.to_cut_rect.unwrap().0.x
-                        - response.clone().unwrap().rect.left_top().x)
-                        / self.shrink_fact.unwrap())
-                        * self.multiplication_factor.unwrap()) as u32,
-                    (((self.to_cut_rect.unwrap().0.y
-                        - response.clone().unwrap().rect.left_top().y)
-                        / self.shrink_fact.unwrap())
-                        * self.multiplication_factor.unwrap()) as u32,
-                    ((w / self.shrink_fact.unwrap()) * self.multiplication_factor.unwrap()) as u32,
-                    ((h / self.shrink_fact.unwrap()) * self.multiplication_factor.unwrap()) as u32,
-                );
-            }
 
-            let image_buffer_cutted = Some(ImageBuffer::from(cutted.clone().into_rgba8()));
-            let im_b = cutted.to_rgba8();
+        //The part of the working image kept by the crop, all of it without a crop
+        pub fn view_image(&self) -> Option<RgbaImage> {
+            let img = self.image_buffer.as_ref()?;
+            Some(match self.crop_area {
+                Some((x, y, w, h)) => image::imageops::crop_imm(img, x, y, w, h).to_image(),
+                None => img.clone(),
+            })
+        }
+
+        //What save and copy write out: the view, beautified when it is enabled
+        pub fn export_image(&self) -> Option<RgbaImage> {
+            let view = self.view_image()?;
+            Some(if self.beautify.enabled { self.beautify.apply(&view) } else { view })
+        }
+
+        //Shows the cropped view in the editor
+        pub fn refresh_view(&mut self, ctx: &egui::Context) {
+            let view = match self.view_image() {
+                Some(v) => v,
+                None => return,
+            };
             let ci = ColorImage::from_rgba_unmultiplied(
-                [im_b.dimensions().0 as usize, im_b.dimensions().1 as usize],
-                im_b.as_bytes(),
+                [view.width() as usize, view.height() as usize],
+                view.as_bytes(),
             );
-            let new_img =
-                ui.ctx()
-                    .load_texture("new image", ImageData::from(ci.clone()), Default::default());
+            self.image = Some(ctx.load_texture("new image", ImageData::from(ci.clone()), Default::default()));
             self.image_texture = Some(ci);
-            self.image = Some(new_img);
+            let mult = self.multiplication_factor.unwrap_or(1.0);
+            self.width = view.width() as f32 / mult;
+            self.height = view.height() as f32 / mult;
+        }
+
+        //The crop is edited on the whole image, starting from the current one
+        pub fn begin_crop(&mut self, ctx: &egui::Context) {
+            self.crop_restore = self.crop_area;
+            self.crop_area = None;
+            self.crop_selection = None;
+            self.refresh_view(ctx);
+        }
+
+        //Keeps the selected part of the image on the canvas where it is drawn,
+        //nothing is thrown away so the crop can be widened again later
+        pub fn apply_crop(&mut self, ctx: &egui::Context, canvas: Rect) {
+            if let (Some(img), Some(rect)) = (&self.image_buffer, self.crop_selection.as_ref().and_then(|s| s.get_rect())) {
+                let (w, h) = img.dimensions();
+                self.crop_area = ImageMapping::new(canvas, w, h)
+                    .pixel_rect(rect)
+                    .filter(|area| *area != (0, 0, w, h));
+            }
+            self.crop_selection = None;
+            self.refresh_view(ctx);
+        }
+
+        pub fn cancel_crop(&mut self, ctx: &egui::Context) {
+            self.crop_area = self.crop_restore;
+            self.crop_selection = None;
+            self.refresh_view(ctx);
+        }
+
+        //Rotates, flips or resizes the whole image, the crop moves along with it.
+        //The previous image is kept so that the change can be undone from the editor
+        pub fn apply_transform(&mut self, ctx: &egui::Context, geometry: Geometry) {
+            let img = match self.image_buffer.take() {
+                Some(img) => img,
+                None => return,
+            };
+            let crop_area = self.crop_area;
+            self.crop_area = crop_area.map(|area| geometry.map_area(area, img.dimensions()));
+            self.image_buffer = Some(geometry.apply(&img));
+            self.transform_undo.push((img, crop_area));
+            self.transform_redo.clear();
+            self.refresh_view(ctx);
+            self.painting.record_transform(geometry);
+        }
+
+        //The resize asked for the shown image, scaled to the whole one when cropped
+        pub fn resize_geometry(&self) -> Option<Geometry> {
+            let (view_w, view_h) = self.view_image()?.dimensions();
+            let (full_w, full_h) = self.image_buffer.as_ref()?.dimensions();
+            let (w, h) = self.resize_settings.target((view_w, view_h));
+            if (w, h) == (view_w, view_h) {
+                return None;
+            }
+            Some(Geometry::Resize(
+                ((full_w as f32 * w as f32 / view_w as f32).round() as u32).max(1),
+                ((full_h as f32 * h as f32 / view_h as f32).round() as u32).max(1),
+                self.resize_settings.filter,
+            ))
+        }
+
+        //Gives the tool in use the color of the image where the eyedropper clicked,
+        //`at` is in fractions of the shown image
+        pub fn pick_color(&mut self, at: Pos2) {
+            let img = match &self.image_buffer {
+                Some(img) => img,
+                None => return,
+            };
+            let (x, y, w, h) = self.crop_area.unwrap_or((0, 0, img.width(), img.height()));
+            let px = x + ((at.x * w as f32) as u32).min(w - 1);
+            let py = y + ((at.y * h as f32) as u32).min(h - 1);
+            let [r, g, b, a] = img.get_pixel(px, py).0;
+            self.painting.set_color(Color32::from_rgba_unmultiplied(r, g, b, a));
+        }
+
+        //Follows the undo and redo buttons of the editor when they reach a transform
+        pub fn check_transform_history(&mut self, ctx: &egui::Context) {
+            let step = match self.painting.take_history_step() {
+                Some(step) => step,
+                None => return,
+            };
+            let (from, to) = match step {
+                HistoryStep::Undo => (&mut self.transform_undo, &mut self.transform_redo),
+                HistoryStep::Redo => (&mut self.transform_redo, &mut self.transform_undo),
+            };
+            if let Some((img, crop)) = from.pop() {
+                if let Some(current) = self.image_buffer.take() {
+                    to.push((current, self.crop_area));
+                }
+                self.image_buffer = Some(img);
+                self.crop_area = crop;
+                self.refresh_view(ctx);
+            }
+        }
 
-            self.width =
-                (self.image.clone().unwrap().size()[0] as f32 + 1.0) / self.multiplication_factor.unwrap();
-            self.height =
-                (self.image.clone().unwrap().size()[1] as f32 + 1.0 )/ self.multiplication_factor.unwrap();
+        //Pixels of the working image per point of the canvas
+        fn crop_scale(&self, canvas: Rect) -> f32 {
+            let (w, h) = self.image_buffer.as_ref().map_or((1, 1), |img| img.dimensions());
+            ImageMapping::new(canvas, w, h).scale().x
+        }
+
+        pub fn set_crop_preset(&mut self, preset: Preset, canvas: Rect) {
+            self.crop_preset = preset;
+            let previous = self.crop_selection.as_ref().and_then(|s| s.get_rect());
+            let mut selection = Selection::new(preset, self.crop_scale(canvas));
+            selection.set_rect(previous.unwrap_or(canvas), canvas);
+            self.crop_selection = Some(selection);
+        }
 
-            self.image_buffer = image_buffer_cutted.clone();
+        //The crop rectangle in pixels of the working image
+        pub fn crop_pixels(&self, canvas: Rect) -> Option<(u32, u32, u32, u32)> {
+            let (w, h) = self.image_buffer.as_ref()?.dimensions();
+            ImageMapping::new(canvas, w, h).pixel_rect(self.crop_selection.as_ref()?.get_rect()?)
+        }
+
+        pub fn set_crop_pixels(&mut self, area: (u32, u32, u32, u32), canvas: Rect) {
+            let scale = self.crop_scale(canvas);
+            let rect = Rect::from_min_size(
+                canvas.min + Vec2::new(area.0 as f32, area.1 as f32) / scale,
+                Vec2::new(area.2 as f32, area.3 as f32) / scale,
+            );
+            if let Some(selection) = self.crop_selection.as_mut() {
+                selection.set_rect(rect, canvas);
+            }
+        }
+
+        //Handles and draws the crop rectangle over the canvas: outside of it the image is
+        //darkened and inside the rule of thirds is shown. Gives true when it is confirmed
+        pub fn crop_ui(&mut self, ui: &egui::Ui, canvas: Rect) -> bool {
+            match self.crop_selection.as_mut() {
+                None => {
+                    //the canvas is known only once the image is drawn, the rectangle
+                    //starts from the crop being edited
+                    let previous = self.crop_restore.map(|(x, y, w, h)| {
+                        let scale = self.crop_scale(canvas);
+                        Rect::from_min_size(
+                            canvas.min + Vec2::new(x as f32, y as f32) / scale,
+                            Vec2::new(w as f32, h as f32) / scale,
+                        )
+                    });
+                    self.set_crop_preset(self.crop_preset, canvas);
+                    if let (Some(rect), Some(selection)) = (previous, self.crop_selection.as_mut()) {
+                        selection.set_rect(rect, canvas);
+                    }
+                }
+                //the canvas moved with the layout, the rectangle follows it
+                Some(selection) if self.crop_canvas != canvas => {
+                    if let Some(rect) = selection.get_rect() {
+                        let transform = emath::RectTransform::from_to(self.crop_canvas, canvas);
+                        selection.set_rect(transform.transform_rect(rect), canvas);
+                    }
+                }
+                Some(_) => {}
+            }
+            self.crop_canvas = canvas;
+
+            let selection = self.crop_selection.as_mut().unwrap();
+            let confirmed = selection.handle_input(ui, canvas);
+            let rect = match selection.get_rect() {
+                Some(rect) => rect,
+                None => return false,
+            };
+
+            let painter = ui.painter().with_clip_rect(canvas);
+            let shade = Color32::from_black_alpha(140);
+            painter.rect_filled(Rect::from_min_max(canvas.min, egui::pos2(canvas.max.x, rect.min.y)), 0.0, shade);
+            painter.rect_filled(Rect::from_min_max(egui::pos2(canvas.min.x, rect.max.y), canvas.max), 0.0, shade);
+            painter.rect_filled(Rect::from_min_max(egui::pos2(canvas.min.x, rect.min.y), egui::pos2(rect.min.x, rect.max.y)), 0.0, shade);
+            painter.rect_filled(Rect::from_min_max(egui::pos2(rect.max.x, rect.min.y), egui::pos2(canvas.max.x, rect.max.y)), 0.0, shade);
+            for third in [1.0 / 3.0, 2.0 / 3.0] {
+                let x = rect.min.x + rect.width() * third;
+                let y = rect.min.y + rect.height() * third;
+                painter.add(egui::Shape::dashed_line(&[egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)], Stroke::new(1.0, Color32::WHITE), 10.0, 5.0));
+                painter.add(egui::Shape::dashed_line(&[egui::pos2(rect.min.x, y), egui::pos2(rect.max.x, y)], Stroke::new(1.0, Color32::WHITE), 10.0, 5.0));
+            }
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::WHITE));
+            if selection.is_resizable() {
+                for (_, handle) in Selection::handle_rects(rect) {
+                    painter.rect(handle, 1.0, Color32::WHITE, Stroke::new(1.0, Color32::GRAY));
+                }
+            }
+            confirmed
         }
+
         pub fn save_img(&mut self, ui: &mut egui::Ui) {
             self.save = true;
+            self.edit_image(ui);
 
-            self.image_name = Some(
-                chrono::offset::Local::now()
-                    .format("%Y-%m-%d_%H_%M_%S")
-                    .to_string(),
-            );
-            self.toasts
-                .as_mut()
-                .unwrap()
-                .success(format!(
-                    "Image saved in {}/{}.{}",
-                    self.filepath
-                        .clone()
+            if let Some(img) = self.export_image() {
+                let result = self.export_to_default_folder(&img);
+                self.notify_export(&result);
+            }
+            self.save = false;
+        }
+
+        //Saves in the default folder with the name built from the naming template
+        pub fn export_to_default_folder(&mut self, img: &RgbaImage) -> Result<ExportedImage, ExportError> {
+            self.capture_counter += 1;
+            let destination = Destination::Directory {
+                dir: self.filepath.clone().unwrap_or(PathBuf::from("./screenshot")),
+                template: self.naming_template.clone(),
+                naming: NamingContext::new(
+                    self.capture_counter,
+                    self.monitor_index(),
+                    self.mode_tag(),
+                    img.width(),
+                    img.height(),
+                ),
+            };
+            export::export_image(img, &destination, &self.export_options())
+        }
+
+        pub fn save_img_with_name(&mut self, ui: &mut egui::Ui) {
+            self.edit_image(ui);
+            let dialog = FileDialog::new()
+                .add_filter(".jpg", &["jpg"])
+                .add_filter(".png", &["png"])
+                .add_filter(".gif", &["gif"])
+                .save_file();
+
+            if let (Some(path), Some(img)) = (dialog, self.export_image()) {
+                let result =
+                    export::export_image(&img, &Destination::File(path), &self.export_options());
+                self.notify_export(&result);
+            }
+        }
+
+        pub fn export_options(&self) -> ExportOptions {
+            ExportOptions {
+                format: self.image_format.unwrap_or(ImageFormat::Jpg),
+            }
+        }
+
+        pub fn notify_export(&mut self, result: &Result<ExportedImage, ExportError>) {
+            match result {
+                Ok(exported) => {
+                    self.image_name = exported
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string());
+                    let _ = self.history.add(HistoryEntry::new(
+                        exported.path.clone(),
+                        exported.width,
+                        exported.height,
+                    ));
+                    self.toasts
+                        .as_mut()
                         .unwrap()
-                        .as_os_str()
-                        .to_str()
+                        .success(format!(
+                            "Image saved in {} ({}x{})",
+                            exported.path.display(),
+                            exported.width,
+                            exported.height
+                        ))
+                        .set_duration(Some(Duration::from_secs(5)));
+                }
+                Err(e) => {
+                    self.toasts
+                        .as_mut()
                         .unwrap()
-                        .to_string(),
-                    self.image_name.clone().unwrap(),
-                    self.image_format_string
-                ))
-                .set_duration(Some(Duration::from_secs(5)));
-
+                        .error(format!("Unable to save the image: {}", e))
+                        .set_duration(Some(Duration::from_secs(5)));
+                }
+            }
             self.show_toast = true;
+        }
+
+        pub fn copy_img(&mut self, ui: &mut egui::Ui) {
             self.edit_image(ui);
 
-            let mod_img = self.image_buffer.clone();
-
-            if mod_img.is_none() == false {
-                if self.current_os == "windows" {
-                    let _ = mod_img.unwrap().save(format!(
-                        "{}/{}.{}",
-                        self.filepath
-                            .clone()
-                            .unwrap()
-                            .as_os_str()
-                            .to_str()
-                            .unwrap()
-                            .to_string(),
-                        self.image_name.clone().unwrap(),
-                        self.image_format_string
-                    ));
-                } else {
-                    let _ = mod_img.unwrap().save(format!(
-                        "{}/{}.{}",
-                        self.filepath
-                            .clone()
-                            .unwrap()
-                            .as_os_str()
-                            .to_str()
-                            .unwrap()
-                            .to_string(),
-                        self.image_name.clone().unwrap(),
-                        self.image_format_string
-                    ));
+            if let Some(img) = self.export_image() {
+                self.copy_to_clipboard(&img);
+            }
+        }
+
+        pub fn copy_to_clipboard(&mut self, img: &RgbaImage) {
+            let copied = Clipboard::new().and_then(|mut clipboard| {
+                clipboard.set_image(arboard::ImageData {
+                    width: img.width() as usize,
+                    height: img.height() as usize,
+                    bytes: img.as_bytes().into(),
+                })
+            });
+            match copied {
+                Ok(()) => {
+                    self.toasts
+                        .as_mut()
+                        .unwrap()
+                        .success("Image copied to clipboard")
+                        .set_duration(Some(Duration::from_secs(5)));
+                }
+                Err(e) => {
+                    self.toasts
+                        .as_mut()
+                        .unwrap()
+                        .error(format!("Unable to copy the image: {}", e))
+                        .set_duration(Some(Duration::from_secs(5)));
                 }
             }
-            self.save = false;
+            self.show_toast = true;
+        }
+
+        pub fn run_after_capture_actions(&mut self, ui: &mut egui::Ui) {
+            if self.auto_save_after_capture {
+                self.save_img(ui);
+            }
+            if self.auto_copy_after_capture {
+                self.copy_img(ui);
+            }
+            //without any other action the capture would be lost, so the editor is kept as fallback
+            if !self.open_editor_after_capture
+                && (self.auto_save_after_capture || self.auto_copy_after_capture)
+            {
+                self.loading_state = LoadingState::NotLoaded;
+                self.image = None;
+                self.selected_window = 1;
+            }
+        }
+
+        pub fn open_in_editor(&mut self, img: RgbaImage) {
+            let mult = self.multiplication_factor.unwrap_or(1.0);
+            self.width = img.width() as f32 / mult;
+            self.height = img.height() as f32 / mult;
+            self.screenshots_taken = Some(img);
+            self.set_image_texture();
+
+            self.circle_pixels = Vec::new();
+            self.square_pixels = Vec::new();
+            self.arrow_pixels = Vec::new();
+            self.text_pixels = Vec::new();
+            self.line_pixels = Vec::new();
+            self.save = true;
+            self.loading_state = LoadingState::NotLoaded;
+            self.selected_window = 5;
+        }
+
+        pub fn open_history_entry(&mut self, path: &Path) -> Option<RgbaImage> {
+            match image::open(path) {
+                Ok(img) => Some(img.to_rgba8()),
+                Err(e) => {
+                    self.toasts
+                        .as_mut()
+                        .unwrap()
+                        .error(format!("Unable to open {}: {}", path.display(), e))
+                        .set_duration(Some(Duration::from_secs(5)));
+                    self.show_toast = true;
+                    None
+                }
+            }
+        }
+
+        pub fn delete_history_entry(&mut self, path: &Path) {
+            let removed = match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => self.history.remove(path),
+            };
+            self.history_thumbnails.remove(path);
+            if let Err(e) = removed {
+                self.toasts
+                    .as_mut()
+                    .unwrap()
+                    .error(format!("Unable to delete {}: {}", path.display(), e))
+                    .set_duration(Some(Duration::from_secs(5)));
+                self.show_toast = true;
+            }
+        }
+
+        pub fn history_thumbnail(
+            &mut self,
+            ctx: &egui::Context,
+            path: &Path,
+        ) -> Option<TextureHandle> {
+            if !self.history_thumbnails.contains_key(path) {
+                let thumbnail = image::open(path).ok().map(|img| {
+                    let thumb = img.thumbnail(160, 90).to_rgba8();
+                    ctx.load_texture(
+                        format!("thumbnail {}", path.display()),
+                        ColorImage::from_rgba_unmultiplied(
+                            [thumb.width() as usize, thumb.height() as usize],
+                            thumb.as_bytes(),
+                        ),
+                        Default::default(),
+                    )
+                });
+                self.history_thumbnails.insert(path.to_path_buf(), thumbnail);
+            }
+            self.history_thumbnails.get(path).cloned().flatten()
+        }
+
+        pub fn select_monitor(&mut self, monitor: &Monitor) {
+            self.screen_to_show = Some(monitor.id);
+            self.screen_size = Some(Vec2::new(monitor.width as f32, monitor.height as f32));
+            self.frame_initial_pos = Some(Pos2::new(monitor.x as f32, monitor.y as f32));
+        }
+
+        //Where the selection overlay is placed: rectangle and window selections can span
+        //the whole virtual desktop, the other modes work on the selected monitor only
+        pub fn overlay_bounds(&self) -> Rect {
+            let monitor = Rect::from_min_size(self.frame_initial_pos.unwrap(), self.screen_size.unwrap());
+            if !matches!(self.selected_mode, ModeOptions::Rectangle | ModeOptions::Window) {
+                return monitor;
+            }
+            match monitors::virtual_bounds(&monitors::all_monitors()) {
+                Some((x, y, width, height)) => Rect::from_min_size(
+                    Pos2::new(x as f32, y as f32),
+                    Vec2::new(width as f32, height as f32),
+                ),
+                None => monitor,
+            }
+        }
+
+        pub fn monitor_index(&self) -> usize {
+            let screens = Screen::all().unwrap_or_default();
+            screens
+                .iter()
+                .position(|s| Some(s.display_info.id) == self.screen_to_show)
+                .map(|i| i + 1)
+                .unwrap_or(1)
+        }
+
+        pub fn mode_tag(&self) -> &'static str {
+            match self.selected_mode {
+                ModeOptions::Rectangle => "rectangle",
+                ModeOptions::FullScreen => "fullscreen",
+                ModeOptions::Scrolling => "scrolling",
+                ModeOptions::AllScreens => "allscreens",
+                ModeOptions::Window => "window",
+            }
         }
 
         pub fn find_true_modifier(json_str: &str) -> Option<&str> {
@@ -459,11 +1325,9 @@ pub mod first_window {
                     Ok(event) => match event.state {
                         HotKeyState::Pressed => {
                             if event.id == self.shortcuts.get_hotkeys()[1].id() {
-  
-                                std::thread::sleep(Duration::from_secs(
-                                    self.selected_timer_numeric,
-                                ));
-                                self.selected_window = 2;
+                                self.start_capture();
+                            } else if event.id == self.shortcuts.get_hotkeys()[6].id() {
+                                self.ready_to_repeat_region = true;
                             }
                         }
                         HotKeyState::Released => {}
@@ -499,6 +1363,8 @@ pub mod first_window {
                                 self.ready_to_save_with_name = true;
                             } else if event.id == self.shortcuts.get_hotkeys()[5].id() {
                                 self.ready_to_crop = true;
+                            } else if event.id == self.shortcuts.get_hotkeys()[6].id() {
+                                self.ready_to_repeat_region = true;
                             } else {
                        
                             }
@@ -506,10 +1372,83 @@ pub mod first_window {
                         HotKeyState::Released => {}
                     },
 
+                    Err(_) => {}
+                }
+            } else if self.selected_window == 12 {
+                match self.open_fw.try_recv() {
+                    Ok(event) => match event.state {
+                        HotKeyState::Pressed => {
+                            if event.id == self.shortcuts.get_hotkeys()[0].id() {
+                                //Exit
+                                self.stop_interval_capture();
+                            }
+                        }
+                        HotKeyState::Released => {}
+                    },
+
+                    Err(_) => {}
+                }
+            } else if self.selected_window == 13 {
+                match self.open_fw.try_recv() {
+                    Ok(event) => match event.state {
+                        HotKeyState::Pressed => {
+                            if event.id == self.shortcuts.get_hotkeys()[7].id() {
+                                self.stop_recording();
+                            } else if event.id == self.shortcuts.get_hotkeys()[0].id() {
+                                //Exit
+                                self.cancel_recording();
+                            }
+                        }
+                        HotKeyState::Released => {}
+                    },
+
+                    Err(_) => {}
+                }
+            } else if self.selected_window == 11 {
+                match self.open_fw.try_recv() {
+                    Ok(event) => match event.state {
+                        HotKeyState::Pressed => {
+                            if event.id == self.shortcuts.get_hotkeys()[0].id() {
+                                //Exit
+                                self.countdown_end = None;
+                                self.selected_window = 1;
+                            }
+                        }
+                        HotKeyState::Released => {}
+                    },
+
                     Err(_) => {}
                 }
             } else if self.selected_window == 6 {
 
+            } else if self.selected_window == 7 {
+                match self.open_fw.try_recv() {
+                    Ok(event) => match event.state {
+                        HotKeyState::Pressed => {
+                            if event.id == self.shortcuts.get_hotkeys()[6].id() {
+                                self.ready_to_repeat_region = true;
+                            }
+                        }
+                        HotKeyState::Released => {}
+                    },
+
+                    Err(_) => {}
+                }
+            } else if self.selected_window == 8 {
+                match self.open_fw.try_recv() {
+                    Ok(event) => match event.state {
+                        HotKeyState::Pressed => {
+                            if event.id == self.shortcuts.get_hotkeys()[0].id() {
+                                self.cancel_scrolling_capture();
+                            } else if event.id == self.shortcuts.get_hotkeys()[1].id() {
+                                self.add_scroll_frame();
+                            }
+                        }
+                        HotKeyState::Released => {}
+                    },
+
+                    Err(_) => {}
+                }
             }
         }
     }
@@ -0,0 +1,199 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    pub timestamp: DateTime<Local>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl HistoryEntry {
+    pub fn new(path: PathBuf, width: u32, height: u32) -> Self {
+        HistoryEntry {
+            path,
+            timestamp: Local::now(),
+            width,
+            height,
+        }
+    }
+
+    //one line of the index: timestamp, width, height and path separated by tabs
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.timestamp.to_rfc3339(),
+            self.width,
+            self.height,
+            self.path.display()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        let timestamp = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+        let width = fields.next()?.parse().ok()?;
+        let height = fields.next()?.parse().ok()?;
+        let path = fields.next()?;
+        if path.is_empty() {
+            return None;
+        }
+        Some(HistoryEntry {
+            path: PathBuf::from(path),
+            timestamp: timestamp.with_timezone(&Local),
+            width,
+            height,
+        })
+    }
+}
+
+pub struct History {
+    index_path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    //a missing or unreadable index simply gives an empty history
+    pub fn load(index_path: &Path) -> Self {
+        let entries = std::fs::read_to_string(index_path)
+            .map(|content| content.lines().filter_map(HistoryEntry::from_line).collect())
+            .unwrap_or_default();
+        History {
+            index_path: index_path.to_path_buf(),
+            entries,
+        }
+    }
+
+    //most recent capture first
+    pub fn get_entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn add(&mut self, entry: HistoryEntry) -> std::io::Result<()> {
+        self.entries.retain(|e| e.path != entry.path);
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        self.save()
+    }
+
+    pub fn remove(&mut self, path: &Path) -> std::io::Result<()> {
+        self.entries.retain(|e| e.path != path);
+        self.save()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.index_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = std::fs::File::create(&self.index_path)?;
+        for e in self.entries.iter() {
+            writeln!(file, "{}", e.to_line())?;
+        }
+        Ok(())
+    }
+}
+
+//Opens the system file manager on the folder containing the capture
+pub fn reveal_in_file_manager(path: &Path) -> std::io::Result<()> {
+    if cfg!(windows) {
+        std::process::Command::new("explorer")
+            .arg(format!("/select,{}", path.display()))
+            .spawn()?;
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open").arg("-R").arg(path).spawn()?;
+    } else {
+        let folder = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        std::process::Command::new("xdg-open").arg(folder).spawn()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a fresh index for each test, so that they can run in parallel
+    fn index_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("history_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("history.tsv")
+    }
+
+    fn entry(name: &str, width: u32) -> HistoryEntry {
+        HistoryEntry::new(PathBuf::from(format!("/captures/{}.png", name)), width, width / 2)
+    }
+
+    #[test]
+    fn index_round_trips() {
+        let path = index_path("round_trip");
+        let mut history = History::load(&path);
+        history.add(entry("first", 100)).unwrap();
+        history.add(HistoryEntry::new(PathBuf::from("/captures/with\ttab.png"), 30, 20)).unwrap();
+
+        let loaded = History::load(&path);
+
+        assert_eq!(loaded.get_entries(), history.get_entries());
+        assert_eq!(loaded.get_entries()[0].path, PathBuf::from("/captures/with\ttab.png"));
+        assert_eq!(loaded.get_entries()[1].width, 100);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_index_is_empty_and_bad_lines_are_skipped() {
+        let path = index_path("bad_lines");
+        assert!(History::load(&path).get_entries().is_empty());
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let good = entry("good", 10).to_line();
+        let content = format!("not a date\t1\t2\t/a.png\n{}\n2024-01-01T00:00:00+00:00\tx\t2\t/b.png\n2024-01-01T00:00:00+00:00\t1\t2\t\n", good);
+        std::fs::write(&path, content).unwrap();
+
+        let loaded = History::load(&path);
+
+        assert_eq!(loaded.get_entries().len(), 1);
+        assert_eq!(loaded.get_entries()[0].path, PathBuf::from("/captures/good.png"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn newest_first_without_duplicates() {
+        let path = index_path("order");
+        let mut history = History::load(&path);
+        history.add(entry("a", 10)).unwrap();
+        history.add(entry("b", 20)).unwrap();
+        history.add(entry("a", 30)).unwrap();
+
+        let widths: Vec<u32> = history.get_entries().iter().map(|e| e.width).collect();
+        assert_eq!(widths, vec![30, 20]);
+
+        history.remove(Path::new("/captures/b.png")).unwrap();
+        assert_eq!(History::load(&path).get_entries().len(), 1);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn oldest_entries_are_trimmed() {
+        let path = index_path("trim");
+        let mut history = History::load(&path);
+        for i in 0..MAX_ENTRIES + 5 {
+            history.add(entry(&i.to_string(), i as u32)).unwrap();
+        }
+
+        let loaded = History::load(&path);
+
+        assert_eq!(loaded.get_entries().len(), MAX_ENTRIES);
+        assert_eq!(loaded.get_entries()[0].width, (MAX_ENTRIES + 4) as u32);
+        assert_eq!(loaded.get_entries().last().unwrap().width, 5);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
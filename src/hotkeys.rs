@@ -61,6 +61,8 @@ impl Hotkeys{
             HotKey::new(Some(Modifiers::CONTROL), Code::KeyC),  //Copy
             HotKey::new(Some(Modifiers::CONTROL), Code::KeyA),  //Save with name
             HotKey::new(Some(Modifiers::CONTROL), Code::KeyG),  //Crop
+            HotKey::new(Some(Modifiers::CONTROL), Code::KeyL),  //Capture last region
+            HotKey::new(Some(Modifiers::CONTROL), Code::KeyR),  //Stop recording
             ],
             hotkeys_strings: vec![
                 ("ctrl".to_string(),"E".to_string()),
@@ -69,6 +71,8 @@ impl Hotkeys{
                 ("ctrl".to_string(),"C".to_string()),
                 ("ctrl".to_string(),"A".to_string()),
                 ("ctrl".to_string(),"G".to_string()),
+                ("ctrl".to_string(),"L".to_string()),
+                ("ctrl".to_string(),"R".to_string()),
             ]
         }
       
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalSettings {
+    pub enabled: bool,
+    pub every_seconds: u64,
+    //0 means until the user stops it
+    pub duration_minutes: u64,
+    pub skip_identical: bool,
+}
+
+impl Default for IntervalSettings {
+    fn default() -> Self {
+        IntervalSettings {
+            enabled: false,
+            every_seconds: 10,
+            duration_minutes: 0,
+            skip_identical: true,
+        }
+    }
+}
+
+//A running interval capture: tells when the next frame is due
+//and which frames are worth saving
+pub struct IntervalSession {
+    settings: IntervalSettings,
+    started: Instant,
+    next_shot: Instant,
+    last_frame: Option<RgbaImage>,
+    pub saved: u32,
+    pub skipped: u32,
+}
+
+impl IntervalSession {
+    //the first frame is due immediately
+    pub fn new(settings: IntervalSettings) -> Self {
+        let now = Instant::now();
+        IntervalSession {
+            settings,
+            started: now,
+            next_shot: now,
+            last_frame: None,
+            saved: 0,
+            skipped: 0,
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        Instant::now() >= self.next_shot
+    }
+
+    pub fn until_next(&self) -> Duration {
+        self.next_shot.saturating_duration_since(Instant::now())
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.settings.duration_minutes == 0 {
+            return None;
+        }
+        let duration = Duration::from_secs(self.settings.duration_minutes * 60);
+        Some(duration.saturating_sub(self.started.elapsed()))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining().is_some_and(|r| r.is_zero())
+    }
+
+    //Schedules the next frame and tells whether `frame` has to be saved:
+    //it is not when it is identical to the previous one and those are skipped
+    pub fn accept(&mut self, frame: &RgbaImage) -> bool {
+        let every = Duration::from_secs(self.settings.every_seconds.max(1));
+        self.next_shot += every;
+        //a late frame does not make the following ones pile up
+        if self.next_shot <= Instant::now() {
+            self.next_shot = Instant::now() + every;
+        }
+
+        if self.settings.skip_identical && self.last_frame.as_ref() == Some(frame) {
+            self.skipped += 1;
+            return false;
+        }
+        self.last_frame = Some(frame.clone());
+        self.saved += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(skip_identical: bool) -> IntervalSettings {
+        IntervalSettings {
+            enabled: true,
+            every_seconds: 60,
+            duration_minutes: 0,
+            skip_identical,
+        }
+    }
+
+    fn frame(value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(4, 3, image::Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn identical_frames_are_skipped() {
+        let mut session = IntervalSession::new(settings(true));
+
+        assert!(session.accept(&frame(1)));
+        assert!(!session.accept(&frame(1)));
+        assert!(session.accept(&frame(2)));
+        //only the previous frame counts, not every one seen
+        assert!(session.accept(&frame(1)));
+        assert!(!session.accept(&frame(1)));
+
+        assert_eq!((session.saved, session.skipped), (3, 2));
+    }
+
+    #[test]
+    fn identical_frames_are_kept_when_asked() {
+        let mut session = IntervalSession::new(settings(false));
+
+        assert!(session.accept(&frame(1)));
+        assert!(session.accept(&frame(1)));
+        assert_eq!((session.saved, session.skipped), (2, 0));
+    }
+
+    #[test]
+    fn next_frame_is_scheduled_after_every_accept() {
+        let mut session = IntervalSession::new(settings(true));
+        assert!(session.is_due());
+
+        session.accept(&frame(1));
+        assert!(!session.is_due());
+        assert!(session.until_next() > Duration::from_secs(59));
+
+        //a skipped frame moves the schedule as well
+        session.accept(&frame(1));
+        assert!(session.until_next() > Duration::from_secs(119));
+    }
+
+    #[test]
+    fn duration_limits_the_session() {
+        let unlimited = IntervalSession::new(settings(true));
+        assert_eq!(unlimited.remaining(), None);
+        assert!(!unlimited.is_over());
+
+        let limited = IntervalSession::new(IntervalSettings {
+            duration_minutes: 2,
+            ..settings(true)
+        });
+        assert!(limited.remaining().unwrap() > Duration::from_secs(119));
+        assert!(!limited.is_over());
+    }
+}
@@ -0,0 +1,74 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Rounding, Stroke, Vec2};
+use image::{Rgba, RgbaImage};
+
+//side of the sampled square, odd so that the pixel under the cursor is in the middle
+pub const LOUPE_PIXELS: u32 = 15;
+//size on screen of every sampled pixel
+pub const LOUPE_ZOOM: f32 = 8.0;
+const LOUPE_OFFSET: f32 = 24.0;
+const TEXT_HEIGHT: f32 = 22.0;
+
+//Square of LOUPE_PIXELS around `center`, pixels outside the image stay transparent
+pub fn sample(image: &RgbaImage, center: (i64, i64)) -> RgbaImage {
+    let half = (LOUPE_PIXELS / 2) as i64;
+    RgbaImage::from_fn(LOUPE_PIXELS, LOUPE_PIXELS, |x, y| {
+        let src_x = center.0 - half + x as i64;
+        let src_y = center.1 - half + y as i64;
+        if src_x < 0 || src_y < 0 || src_x >= image.width() as i64 || src_y >= image.height() as i64 {
+            Rgba([0, 0, 0, 0])
+        } else {
+            *image.get_pixel(src_x as u32, src_y as u32)
+        }
+    })
+}
+
+pub fn center_color(sample: &RgbaImage) -> Rgba<u8> {
+    *sample.get_pixel(LOUPE_PIXELS / 2, LOUPE_PIXELS / 2)
+}
+
+pub fn hex_color(color: Rgba<u8>) -> String {
+    format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+}
+
+//Where to draw the loupe: below right of the cursor, moved to the other side
+//when it would go out of the bounds
+pub fn loupe_rect(cursor: Pos2, bounds: Rect) -> Rect {
+    let size = Vec2::new(
+        LOUPE_PIXELS as f32 * LOUPE_ZOOM,
+        LOUPE_PIXELS as f32 * LOUPE_ZOOM + TEXT_HEIGHT,
+    );
+    let mut min = cursor + Vec2::splat(LOUPE_OFFSET);
+    if min.x + size.x > bounds.max.x {
+        min.x = cursor.x - LOUPE_OFFSET - size.x;
+    }
+    if min.y + size.y > bounds.max.y {
+        min.y = cursor.y - LOUPE_OFFSET - size.y;
+    }
+    Rect::from_min_size(min, size)
+}
+
+//Draws the zoomed pixels with the one under the cursor outlined and the
+//text (usually its position and color) below them
+pub fn paint(painter: &Painter, rect: Rect, sample: &RgbaImage, text: &str) {
+    painter.rect_filled(rect, Rounding::same(2.0), Color32::from_black_alpha(200));
+    for (x, y, p) in sample.enumerate_pixels() {
+        let cell = Rect::from_min_size(
+            rect.min + Vec2::new(x as f32, y as f32) * LOUPE_ZOOM,
+            Vec2::splat(LOUPE_ZOOM),
+        );
+        painter.rect_filled(cell, Rounding::ZERO, Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]));
+    }
+    let middle = (LOUPE_PIXELS / 2) as f32 * LOUPE_ZOOM;
+    painter.rect_stroke(
+        Rect::from_min_size(rect.min + Vec2::splat(middle), Vec2::splat(LOUPE_ZOOM)),
+        Rounding::ZERO,
+        Stroke::new(1.0, Color32::WHITE),
+    );
+    painter.text(
+        Pos2::new(rect.center().x, rect.max.y - TEXT_HEIGHT / 2.0),
+        Align2::CENTER_CENTER,
+        text,
+        FontId::monospace(13.0),
+        Color32::WHITE,
+    );
+}
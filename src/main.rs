@@ -1,15 +1,33 @@
 mod post_processing;
 mod hotkeys;
+mod naming;
+mod export;
+mod history;
+mod monitors;
+mod stitching;
+mod selection;
+mod loupe;
+mod window_capture;
+mod interval;
+mod recording;
+mod cursor;
+mod coords;
+mod transform;
+mod beautify;
+mod palette;
+mod style;
+use crate::export::ImageFormat;
 use crate::post_processing::PpOptions;
 use crate::post_processing::View;
+use crate::selection::{Preset, Selection, PRESETS};
+use crate::transform::{Geometry, RESIZE_FILTERS};
+use crate::style::Style;
 
 use egui::CursorIcon;
 
 use hotkeys::CustomizeHotkey;
 use hotkeys::Hotkeys;
-use image::EncodableLayout;
 use rfd::FileDialog;
-use arboard::Clipboard;
 use egui_notify::Toasts;
 mod functions;
 use eframe::{
@@ -19,6 +37,7 @@ use eframe::{
 use egui::{epaint::RectShape, Pos2, Rect, Rounding, Shape, Stroke, TextureHandle, Vec2};
 
 use screenshots::Screen;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -26,10 +45,18 @@ use global_hotkey::{
     GlobalHotKeyEvent, GlobalHotKeyEventReceiver, GlobalHotKeyManager,
 };
 
+const APP_TITLE: &str = "Screen Grabbing Utility";
+const COUNTDOWN_SIZE: Vec2 = Vec2::new(220.0, 110.0);
+//the interval panel disappears a bit earlier than every frame so that it is not captured
+const INTERVAL_HIDE_BEFORE_SHOT: Duration = Duration::from_millis(300);
+
 #[derive(PartialEq, Debug)]
 enum ModeOptions {
     Rectangle,
     FullScreen,
+    Scrolling,
+    AllScreens,
+    Window,
 }
 #[derive(PartialEq, Debug)]
 enum Shapes {
@@ -39,42 +66,23 @@ enum Shapes {
     Square,
 }
 
-#[derive(PartialEq, Debug)]
-enum TimerOptions {
-    NoTimer,
-    ThreeSeconds,
-    FiveSeconds,
-    TenSeconds,
-}
-
 #[derive(PartialEq, Debug)]
 enum LoadingState {
     Loaded,
     NotLoaded,
 }
 
-#[derive(PartialEq, Debug)]
-enum ImageFormat {
-    Jpg,
-    Png,
-    Gif,
-}
-
 
 fn main() -> Result<(), eframe::Error> {
     let mut filepath = Some(PathBuf::new());
 
-    let current_os = if cfg!(unix) {
+    if cfg!(unix) {
         let _ = std::fs::create_dir("./screenshot");
         filepath = Some(PathBuf::from("./screenshot"));
-        "unix"
     } else if cfg!(windows) {
         let _ = std::fs::create_dir(".//screenshot");
         filepath = Some(PathBuf::from(".//screenshot"));
-        "windows"
-    } else {
-        "unknown"
-    };
+    }
 
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(680.0, 480.0)),
@@ -87,12 +95,15 @@ fn main() -> Result<(), eframe::Error> {
     let manager = GlobalHotKeyManager::new().unwrap();
     let shortcuts = Hotkeys::new();
     manager.register_all(shortcuts.get_hotkeys().as_slice()).unwrap();
-    let p=post_processing::Painting::default();
+    let mut p=post_processing::Painting::default();
+    p.set_recent_colors(palette::RecentColors::load(filepath.clone().unwrap().join("colors.tsv").as_path()));
+    let history = history::History::load(filepath.clone().unwrap().join("history.tsv").as_path());
+    let beautify_presets = beautify::BeautifyPresets::load(filepath.clone().unwrap().join("beautify.tsv").as_path());
 
     let openfw = GlobalHotKeyEvent::receiver();
 
     eframe::run_native(
-        "Screen Grabbing Utility",
+        APP_TITLE,
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
@@ -106,7 +117,6 @@ fn main() -> Result<(), eframe::Error> {
                 image_format: Some(ImageFormat::Jpg),
                 image_format_string: "jpg".to_string(),
                 pp_option: None,
-                current_os: current_os.to_string(),
                 multiplication_factor: None,
                 screen_size: None,
                 loading_state: LoadingState::NotLoaded,
@@ -114,16 +124,50 @@ fn main() -> Result<(), eframe::Error> {
                 image_texture: None,
                 image_buffer: None,
                 filepath: filepath,
+                naming_template: naming::DEFAULT_TEMPLATE.to_string(),
+                capture_counter: 0,
+                open_editor_after_capture: true,
+                auto_save_after_capture: false,
+                auto_copy_after_capture: false,
+                fresh_capture: false,
+                history,
+                history_thumbnails: HashMap::new(),
+                scroll_area: None,
+                scroll_panel_pos: Pos2::new(0.0, 0.0),
+                scroll_frames: Vec::new(),
+                freeze_screen: false,
+                frozen_frame: None,
+                frozen_texture: None,
+                frozen_pointer: None,
+                capture_cursor: false,
+                cursor_ring: false,
+                loupe_frame: None,
+                backdrop_job: None,
+                backdrop_ready: false,
                 selected_mode: ModeOptions::Rectangle,
                 selected_mode_string: "Rectangle".to_string(),
-                selected_timer: TimerOptions::NoTimer,
-                selected_timer_string: "No timer".to_string(),
-                selected_timer_numeric: 0 as u64,
+                timer_seconds: 0,
+                countdown_end: None,
+                interval_settings: interval::IntervalSettings::default(),
+                interval_session: None,
+                interval_panel_pos: Pos2::new(0.0, 0.0),
+                recording_settings: recording::RecordingSettings::default(),
+                recording: None,
+                recording_panel_pos: None,
+                recording_indicator_pos: Pos2::new(0.0, 0.0),
+                recording_job: None,
                 selected_shape: Shapes::None,
                 selected_shape_string: "Select a shape!".to_string(),
                 selected_window: 1,
-                mouse_pos: Option::Some(egui::pos2(-1.0, -1.0)),
-                mouse_pos_f: Option::Some(egui::pos2(-1.0, -1.0)),
+                selection: Selection::default(),
+                selection_preset: Preset::Free,
+                exact_region: (0, 0, 1280, 720),
+                last_region: None,
+                ready_to_repeat_region: false,
+                mode_before_last_region: None,
+                window_list: Vec::new(),
+                window_list_loaded: false,
+                picked_app_window: None,
                 rect_pos: egui::pos2(0.0, 0.0),
                 rect_pos_f: egui::pos2(0.0, 0.0),
                 open_fw: openfw.clone(),
@@ -131,7 +175,6 @@ fn main() -> Result<(), eframe::Error> {
                 painting: p,
                 width: 0.0,
                 height: 0.0,
-                mult_factor: None,
                 cut_clicked: false,
                 cropped:false,
                 circle_pixels: Vec::new(),
@@ -146,14 +189,20 @@ fn main() -> Result<(), eframe::Error> {
                 ready_to_crop: false,
                 customizing_hotkey: usize::MAX,
                 new_hotkey: CustomizeHotkey::default(),
-                to_cut_rect:None,
-                shrink_fact:None,
                 shortcuts: shortcuts,
                 manager: manager,
                 ready_to_cut:None,
-                is_pointer_on_cut_window: false,
-                set_wh_window:true,
-                dim_bool: false,
+                crop_area: None,
+                crop_restore: None,
+                crop_selection: None,
+                crop_canvas: Rect::NOTHING,
+                crop_preset: Preset::Free,
+                resize_settings: transform::ResizeSettings::default(),
+                transform_undo: Vec::new(),
+                transform_redo: Vec::new(),
+                beautify: beautify::Beautify::default(),
+                beautify_presets,
+                beautify_preset_name: String::new(),
                 first_time:true,
             })
         }),
@@ -171,7 +220,6 @@ struct FirstWindow {
     image_format: Option<ImageFormat>,
     image_format_string: String,
     pp_option: Option<PpOptions>,
-    current_os: String,
     multiplication_factor: Option<f32>,
     screen_size: Option<Vec2>,
     loading_state: LoadingState,
@@ -179,16 +227,54 @@ struct FirstWindow {
     image_texture: Option<egui::ColorImage>,
     image_buffer: Option<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>>,
     filepath: Option<PathBuf>,
+    naming_template: String,
+    capture_counter: u32,
+    open_editor_after_capture: bool,
+    auto_save_after_capture: bool,
+    auto_copy_after_capture: bool,
+    //set when a new capture reaches the editor, the after-capture actions run once for it
+    fresh_capture: bool,
+    history: history::History,
+    history_thumbnails: HashMap<PathBuf, Option<TextureHandle>>,
+    scroll_area: Option<Rect>,
+    scroll_panel_pos: Pos2,
+    scroll_frames: Vec<image::RgbaImage>,
+    freeze_screen: bool,
+    frozen_frame: Option<image::RgbaImage>,
+    frozen_texture: Option<TextureHandle>,
+    frozen_pointer: Option<(Pos2, bool)>,
+    capture_cursor: bool,
+    cursor_ring: bool,
+    //the screen under the overlay, grabbed while the window is hidden, that the loupe shows
+    loupe_frame: Option<image::RgbaImage>,
+    backdrop_job: Option<std::thread::JoinHandle<Option<image::RgbaImage>>>,
+    backdrop_ready: bool,
     selected_mode: ModeOptions,
     selected_mode_string: String,
-    selected_timer: TimerOptions,
-    selected_timer_string: String,
-    selected_timer_numeric: u64,
+    timer_seconds: u64,
+    countdown_end: Option<std::time::Instant>,
+    interval_settings: interval::IntervalSettings,
+    interval_session: Option<interval::IntervalSession>,
+    interval_panel_pos: Pos2,
+    recording_settings: recording::RecordingSettings,
+    recording: Option<recording::Recording>,
+    recording_panel_pos: Option<Pos2>,
+    //where the small stop indicator goes when the panel would be recorded
+    recording_indicator_pos: Pos2,
+    recording_job: Option<std::thread::JoinHandle<Result<export::ExportedImage, recording::RecordingError>>>,
     selected_shape: Shapes,
     selected_shape_string: String,
     selected_window: usize,
-    mouse_pos: Option<Pos2>,
-    mouse_pos_f: Option<Pos2>,
+    selection: Selection,
+    selection_preset: Preset,
+    exact_region: (i32, i32, u32, u32),
+    last_region: Option<Rect>,
+    ready_to_repeat_region: bool,
+    //the mode chosen by the user while a last region capture uses the rectangle one
+    mode_before_last_region: Option<(ModeOptions, String)>,
+    window_list: Vec<window_capture::WindowInfo>,
+    window_list_loaded: bool,
+    picked_app_window: Option<u32>,
     rect_pos: Pos2,
     rect_pos_f: Pos2,
     open_fw: GlobalHotKeyEventReceiver,
@@ -196,14 +282,13 @@ struct FirstWindow {
     painting: post_processing::Painting,
     width: f32,
     height: f32,
-    mult_factor: Option<(f32, f32)>,
     cut_clicked: bool,
     cropped:bool,
-    circle_pixels: Vec<(Pos2, f32, Color32)>,
-    square_pixels: Vec<(Rect, Color32)>,
-    arrow_pixels: Vec<(Vec<Pos2>, Color32)>,
-    text_pixels: Vec<(Pos2, Color32, String)>,
-    line_pixels: Vec<(Vec<Pos2>, Color32)>,
+    circle_pixels: Vec<(Pos2, f32, Style)>,
+    square_pixels: Vec<(Rect, Style)>,
+    arrow_pixels: Vec<(Vec<Pos2>, Style)>,
+    text_pixels: Vec<(Pos2, Style, String)>,
+    line_pixels: Vec<(Vec<Pos2>, Style)>,
     save:bool,
     ready_to_save:bool,
     ready_to_save_with_name: bool,
@@ -211,14 +296,20 @@ struct FirstWindow {
     ready_to_crop: bool,
     customizing_hotkey:usize,
     new_hotkey: CustomizeHotkey,
-    to_cut_rect:Option<(Pos2, Pos2)>,
-    shrink_fact:Option<f32>,
     shortcuts: Hotkeys,
     manager: GlobalHotKeyManager,
     ready_to_cut:Option<bool>,
-    is_pointer_on_cut_window: bool,
-    set_wh_window:bool,
-    dim_bool:bool,
+    crop_area: Option<(u32, u32, u32, u32)>,
+    crop_restore: Option<(u32, u32, u32, u32)>,
+    crop_selection: Option<Selection>,
+    crop_canvas: Rect,
+    crop_preset: Preset,
+    resize_settings: transform::ResizeSettings,
+    transform_undo: Vec<transform::Snapshot>,
+    transform_redo: Vec<transform::Snapshot>,
+    beautify: beautify::Beautify,
+    beautify_presets: beautify::BeautifyPresets,
+    beautify_preset_name: String,
     first_time:bool,
     
 }
@@ -230,10 +321,12 @@ impl eframe::App for FirstWindow {
             
         let screens=Screen::all().unwrap();
 
-        if self.screen_to_show.is_none(){
-            self.screen_to_show=Some(screens[0].display_info.id);
-            self.screen_size=Some(Vec2::new(screens[0].display_info.width as f32, screens[0].display_info.height as f32));
-            self.frame_initial_pos=Some(Pos2::new(screens[0].display_info.x as f32, screens[0].display_info.y as f32));
+        //falls back to the primary monitor also when the selected one has been disconnected
+        if self.screen_to_show.is_none() || !screens.iter().any(|s| Some(s.display_info.id)==self.screen_to_show){
+            let all_monitors = monitors::all_monitors();
+            if let Some(primary) = monitors::primary(&all_monitors) {
+                self.select_monitor(&primary);
+            }
         }
         self.number_of_screens=Some(screens.len());
         if self.multiplication_factor.is_none() {
@@ -245,15 +338,34 @@ impl eframe::App for FirstWindow {
 
          
 
+        //the last region hotkey pressed in the editor or in the history, handled before
+        //they are drawn again so that the capture starts in this same frame
+        if self.ready_to_repeat_region && (self.selected_window == 5 || self.selected_window == 7) {
+            self.ready_to_repeat_region = false;
+            if self.repeat_last_region() {
+                frame.set_window_size(Vec2::new(0.0, 0.0));
+            }
+        }
+
         if self.selected_window == 1 {
+            self.restore_mode();
             self.hotkey_listener();
-            self.mouse_pos=Some(Pos2::new(-1.0, -1.0));
-            self.mouse_pos_f=Some(Pos2::new(-1.0, -1.0));
+            self.selection = Selection::new(self.selection_preset, self.multiplication_factor.unwrap_or(1.0));
             self.rect_pos =  egui::pos2(0.0, 0.0);
             self.rect_pos_f =  egui::pos2(0.0, 0.0);
+            self.frozen_frame = None;
+            self.frozen_texture = None;
+            self.loupe_frame = None;
+            self.backdrop_ready = false;
+            self.window_list_loaded = false;
+            self.check_recording_job();
             frame.set_decorations(true); 
             frame.set_window_size(egui::vec2(680.0, 480.0)); 
 
+            if self.show_toast{
+                self.toasts.as_mut().unwrap().show(ctx);
+            }
+
             egui::CentralPanel::default().show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.add_space(20.0); 
@@ -263,8 +375,7 @@ impl eframe::App for FirstWindow {
                         .on_hover_text(self.shortcuts.get_hotkey_strings_formatted(1))
                         .clicked()
                     {
-                        std::thread::sleep(Duration::from_secs(self.selected_timer_numeric));
-                        self.selected_window = 2;
+                        self.start_capture();
                     }
 
                     egui::ComboBox::from_id_source("mode_Combobox")
@@ -293,60 +404,57 @@ impl eframe::App for FirstWindow {
                             {
                                 self.selected_mode_string = "Full Screen".to_string();
                             };
-                        });
-
-                    egui::ComboBox::from_id_source("timer_Combobox")
-                        .width(200.0)
-                        .selected_text(
-                            RichText::new(format!("{}", self.selected_timer_string)).size(30.0),
-                        )
-                        .show_ui(ui, |ui| {
-                            if ui
-                                .selectable_value(
-                                    &mut self.selected_timer,
-                                    TimerOptions::NoTimer,
-                                    RichText::new("No Timer").size(30.0),
-                                )
-                                .clicked()
-                            {
-                                self.selected_timer_string = "No Timer".to_string();
-                                self.selected_timer_numeric=0;
-                            };
-
                             if ui
                                 .selectable_value(
-                                    &mut self.selected_timer,
-                                    TimerOptions::ThreeSeconds,
-                                    RichText::new("3 Seconds").size(30.0),
+                                    &mut self.selected_mode,
+                                    ModeOptions::AllScreens,
+                                    RichText::new("All Screens").size(30.0),
                                 )
                                 .clicked()
                             {
-                                self.selected_timer_string = "3 Seconds".to_string();
-                                self.selected_timer_numeric = 3;
+                                self.selected_mode_string = "All Screens".to_string();
                             };
                             if ui
                                 .selectable_value(
-                                    &mut self.selected_timer,
-                                    TimerOptions::FiveSeconds,
-                                    RichText::new("5 Seconds").size(30.0),
+                                    &mut self.selected_mode,
+                                    ModeOptions::Scrolling,
+                                    RichText::new("Scrolling").size(30.0),
                                 )
                                 .clicked()
                             {
-                                self.selected_timer_string = "5 Seconds".to_string();
-                                self.selected_timer_numeric = 5;
+                                self.selected_mode_string = "Scrolling".to_string();
                             };
                             if ui
                                 .selectable_value(
-                                    &mut self.selected_timer,
-                                    TimerOptions::TenSeconds,
-                                    RichText::new("10 Seconds").size(30.0),
+                                    &mut self.selected_mode,
+                                    ModeOptions::Window,
+                                    RichText::new("Window").size(30.0),
                                 )
                                 .clicked()
                             {
-                                self.selected_timer_string = "10 Seconds".to_string();
-                                self.selected_timer_numeric = 10;
+                                self.selected_mode_string = "Window".to_string();
+                                self.refresh_window_list();
                             };
                         });
+
+                    let timer_text = if self.timer_seconds == 0 {
+                        "No Timer".to_string()
+                    } else {
+                        format!("{} Seconds", self.timer_seconds)
+                    };
+                    egui::ComboBox::from_id_source("timer_Combobox")
+                        .width(200.0)
+                        .selected_text(RichText::new(timer_text).size(30.0))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.timer_seconds, 0, RichText::new("No Timer").size(30.0));
+                            for seconds in [3, 5, 10] {
+                                ui.selectable_value(&mut self.timer_seconds, seconds, RichText::new(format!("{} Seconds", seconds)).size(30.0));
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new("Custom").size(20.0));
+                                ui.add(egui::DragValue::new(&mut self.timer_seconds).clamp_range(0..=3600).suffix(" s"));
+                            });
+                        });
                     if ui
                         .add_sized(
                             [50., 50.],
@@ -357,7 +465,113 @@ impl eframe::App for FirstWindow {
                         self.selected_window = 6;
                     }
                 });
-                ui.add_space(150.0);
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    if ui.button(RichText::new("🕘 History").size(20.0)).clicked() {
+                        self.selected_window = 7;
+                    }
+                    if ui
+                        .add_enabled(self.last_region.is_some(), egui::Button::new(RichText::new("↻ Last region").size(20.0)))
+                        .on_hover_text(self.shortcuts.get_hotkey_strings_formatted(6))
+                        .clicked()
+                    {
+                        self.ready_to_repeat_region = true;
+                    }
+                    ui.add_space(20.0);
+                    ui.label(RichText::new("Selection").size(20.0));
+                    egui::ComboBox::from_id_source("preset_Combobox")
+                        .selected_text(RichText::new(self.selection_preset.label()).size(20.0))
+                        .show_ui(ui, |ui| {
+                            for preset in PRESETS {
+                                ui.selectable_value(&mut self.selection_preset, preset, RichText::new(preset.label()).size(20.0));
+                            }
+                        });
+                });
+                if self.selected_mode == ModeOptions::Rectangle {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.label(RichText::new("Exact region").size(20.0));
+                        ui.label("x");
+                        ui.add(egui::DragValue::new(&mut self.exact_region.0));
+                        ui.label("y");
+                        ui.add(egui::DragValue::new(&mut self.exact_region.1));
+                        ui.label("w");
+                        ui.add(egui::DragValue::new(&mut self.exact_region.2).clamp_range(1..=u16::MAX));
+                        ui.label("h");
+                        ui.add(egui::DragValue::new(&mut self.exact_region.3).clamp_range(1..=u16::MAX));
+                        if ui.button(RichText::new("Capture region").size(20.0)).on_hover_text("Physical pixels of the virtual desktop").clicked() {
+                            frame.set_window_size(Vec2::new(0.0, 0.0));
+                            self.select_exact_region();
+                        }
+                    });
+                }
+                if self.selected_mode == ModeOptions::Window {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        let picked = self.window_list.iter().find(|w| Some(w.id) == self.picked_app_window);
+                        egui::ComboBox::from_id_source("window_Combobox")
+                            .width(380.0)
+                            .selected_text(RichText::new(picked.map_or("Pick a window".to_string(), |w| w.label())).size(20.0))
+                            .show_ui(ui, |ui| {
+                                for w in self.window_list.iter() {
+                                    ui.selectable_value(&mut self.picked_app_window, Some(w.id), w.label());
+                                }
+                            });
+                        if ui.button(RichText::new("⟳").size(20.0)).on_hover_text("Refresh the list").clicked() {
+                            self.refresh_window_list();
+                        }
+                        if ui.add_enabled(self.picked_app_window.is_some(), egui::Button::new(RichText::new("Capture window").size(20.0))).clicked() {
+                            if let Some(w) = self.window_list.iter().find(|w| Some(w.id) == self.picked_app_window).cloned() {
+                                frame.set_window_size(Vec2::new(0.0, 0.0));
+                                self.select_app_window(&w);
+                            }
+                        }
+                    });
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    if ui.checkbox(&mut self.interval_settings.enabled, RichText::new("Interval capture").size(20.0))
+                        .on_hover_text("Saves a frame of the selection every few seconds, not available for scrolling captures")
+                        .changed()
+                    {
+                        self.recording_settings.enabled = false;
+                    }
+                    if self.interval_settings.enabled {
+                        ui.label("every");
+                        ui.add(egui::DragValue::new(&mut self.interval_settings.every_seconds).clamp_range(1..=3600).suffix(" s"));
+                        ui.label("for");
+                        ui.add(egui::DragValue::new(&mut self.interval_settings.duration_minutes).clamp_range(0..=1440).suffix(" min"))
+                            .on_hover_text("0 to go on until stopped");
+                        ui.checkbox(&mut self.interval_settings.skip_identical, "Skip identical frames");
+                    }
+                });
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    if ui.checkbox(&mut self.recording_settings.enabled, RichText::new("Record animation").size(20.0))
+                        .on_hover_text("Records the selection until stopped, not available for scrolling captures")
+                        .changed()
+                    {
+                        self.interval_settings.enabled = false;
+                    }
+                    if self.recording_settings.enabled {
+                        ui.add(egui::DragValue::new(&mut self.recording_settings.fps).clamp_range(1..=30).suffix(" fps"));
+                        ui.label("max");
+                        ui.add(egui::DragValue::new(&mut self.recording_settings.max_seconds).clamp_range(1..=600).suffix(" s"));
+                        egui::ComboBox::from_id_source("recording_format_Combobox")
+                            .selected_text(self.recording_settings.format.label())
+                            .show_ui(ui, |ui| {
+                                for format in [recording::RecordingFormat::Gif, recording::RecordingFormat::Apng] {
+                                    ui.selectable_value(&mut self.recording_settings.format, format, format.label());
+                                }
+                            });
+                    }
+                });
+                ui.add_space(20.0);
                 ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
     
                     ui.label(
@@ -365,68 +579,148 @@ impl eframe::App for FirstWindow {
                         );
                 });
             });
+            if self.ready_to_repeat_region {
+                self.ready_to_repeat_region = false;
+                if self.select_last_region() {
+                    frame.set_window_size(Vec2::new(0.0, 0.0));
+                }
+            }
+        } else if self.selected_window == 2 && self.needs_backdrop() {
+            //the window has to disappear before the desktop is grabbed
+            frame.set_window_size(Vec2::new(0.0, 0.0));
+            self.selected_window = 9;
         } else if self.selected_window == 2 {
             self.hotkey_listener();
             frame.set_decorations(false);
-            frame.set_window_size(self.screen_size.unwrap());
-            frame.set_window_pos(self.frame_initial_pos.unwrap());
+            let overlay = self.overlay_bounds();
+            frame.set_window_size(overlay.size());
+            frame.set_window_pos(overlay.min);
 
             self.multiplication_factor=frame.info().native_pixels_per_point;
                         
             match self.selected_mode {
-                ModeOptions::Rectangle => {
+                ModeOptions::Rectangle | ModeOptions::Scrolling => {
                     egui::Area::new("my_area")
                         .fixed_pos(egui::pos2(0.0, 0.0))
                         .show(ctx, |ui| {
-                            if self.current_os=="unix"{
-                                ui.add_space(50.0);
+                            if let Some(backdrop) = self.frozen_backdrop(ui.ctx()) {
+                                ui.painter().image(
+                                    backdrop,
+                                    Rect::from_min_size(Pos2::ZERO, overlay.size()),
+                                    Rect::from_min_max(Pos2::ZERO, egui::pos2(1.0, 1.0)),
+                                    Color32::WHITE,
+                                );
                             }
-                            
                             ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
                                 ui.label(RichText::new(format!("{} to go back", self.shortcuts.get_hotkey_strings_formatted(0))).size(25.0).color(egui::Color32::WHITE));
                             });
-                            ui.ctx()
-                                .output_mut(|i| i.cursor_icon = CursorIcon::Crosshair);
-
-                            if ui.input(|i| {
-                                i.pointer.any_down()
-                                    && self.mouse_pos.unwrap()[0] == -1.0
-                                    && self.mouse_pos.unwrap()[1] == -1.0
-                            }) {
-                                self.mouse_pos = ui.input(|i| i.pointer.interact_pos());
-                                
-                                }
-                            if self.mouse_pos.unwrap()[0] != -1.0
-                                && self.mouse_pos.unwrap()[1] != -1.0
-                            {
-                                self.mouse_pos_f = ui.input(|i| i.pointer.latest_pos());
-                                self.define_rectangle();
-                            }
-                            if ui.input(|i| i.pointer.any_released()) {
+                            ui.label(RichText::new("Drag the handles to adjust, arrows to move, Enter or double-click to capture").size(18.0).color(egui::Color32::WHITE));
+
+                            if self.update_selection(ui, Rect::from_min_size(Pos2::ZERO, overlay.size())) {
                                 frame.set_window_size(Vec2::new(0.0, 0.0));
 
                                 self.selected_window = 3; 
                             }
 
-                            ui.painter().add(Shape::Rect(RectShape::new(
-                                Rect::from_min_max(self.rect_pos, self.rect_pos_f),
-                                Rounding::default(),
-                                Color32::TRANSPARENT,
-                                Stroke::new(2.0, Color32::GRAY),
-                            )));
+                            if let Some(rect) = self.selection.get_rect() {
+                                ui.painter().add(Shape::Rect(RectShape::new(
+                                    rect,
+                                    Rounding::default(),
+                                    Color32::TRANSPARENT,
+                                    Stroke::new(2.0, Color32::GRAY),
+                                )));
+                                for (_, handle) in Selection::handle_rects(rect).into_iter().filter(|_| self.selection.is_resizable()) {
+                                    ui.painter().add(Shape::Rect(RectShape::new(
+                                        handle,
+                                        Rounding::same(1.0),
+                                        Color32::WHITE,
+                                        Stroke::new(1.0, Color32::GRAY),
+                                    )));
+                                }
+                            }
+                            self.paint_selection_info(ui, overlay);
+                        });
+                }
+                ModeOptions::Window => {
+                    if !self.window_list_loaded {
+                        self.refresh_window_list();
+                    }
+                    egui::Area::new("window_area")
+                        .fixed_pos(egui::pos2(0.0, 0.0))
+                        .show(ctx, |ui| {
+                            ui.label(RichText::new(format!("Click on a window to capture it, {} to go back", self.shortcuts.get_hotkey_strings_formatted(0))).size(25.0).color(egui::Color32::WHITE));
+
+                            let mult = self.multiplication_factor.unwrap_or(1.0);
+                            let hovered = ui
+                                .input(|i| i.pointer.hover_pos())
+                                .and_then(|p| window_capture::window_at(&self.window_list, overlay.min + p.to_vec2(), mult))
+                                .cloned();
+                            if let Some(w) = hovered {
+                                ui.ctx().output_mut(|i| i.cursor_icon = CursorIcon::PointingHand);
+                                ui.painter().add(Shape::Rect(RectShape::new(
+                                    w.logical_rect(mult).translate(-overlay.min.to_vec2()),
+                                    Rounding::default(),
+                                    Color32::from_rgba_unmultiplied(40, 110, 180, 60),
+                                    Stroke::new(2.0, Color32::from_rgb(40, 110, 180)),
+                                )));
+                                if ui.input(|i| i.pointer.primary_clicked()) {
+                                    frame.set_window_size(Vec2::new(0.0, 0.0));
+                                    self.select_app_window(&w);
+                                }
+                            }
                         });
                 }
-                ModeOptions::FullScreen => {
+                ModeOptions::FullScreen | ModeOptions::AllScreens => {
                     frame.set_window_size(Vec2::new(0.0, 0.0));
 
                     self.selected_window = 3;
                 }
             }
+        } else if self.selected_window == 11 {
+            self.hotkey_listener();
+            frame.set_decorations(false);
+            frame.set_window_size(COUNTDOWN_SIZE);
+            frame.set_window_pos(self.frame_initial_pos.unwrap() + Vec2::new(self.screen_size.unwrap().x - COUNTDOWN_SIZE.x - 20.0, 20.0));
+
+            match self.countdown_remaining() {
+                Some(remaining) => {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        ui.vertical_centered(|ui| {
+                            ui.label(RichText::new(remaining.as_secs_f32().ceil().to_string()).size(50.0).color(Color32::WHITE));
+                            ui.label(RichText::new(format!("{} to cancel", self.shortcuts.get_hotkey_strings_formatted(0))).size(14.0).color(Color32::GRAY));
+                        });
+                    });
+                }
+                None => {
+                    //the countdown window must not end up in the capture
+                    frame.set_window_size(Vec2::new(0.0, 0.0));
+                    self.countdown_end = None;
+                    self.selected_window = 2;
+                }
+            }
+        } else if self.selected_window == 9 {
+            self.selected_window = 10;
+        } else if self.selected_window == 10 {
+            //the window stays hidden until the screen under it has been grabbed
+            if self.check_backdrop_grab() {
+                self.selected_window = 2;
+            } else {
+                ctx.request_repaint_after(std::time::Duration::from_millis(20));
+            }
         } else if self.selected_window == 3 {
             self.selected_window = 4;
         } else if self.selected_window == 4 {
             self.take_screenshot();
-            self.selected_window = 5;
+            if self.selected_mode == ModeOptions::Scrolling {
+                self.selected_window = 8;
+            } else if self.interval_settings.enabled {
+                self.start_interval_capture();
+            } else if self.recording_settings.enabled {
+                self.start_recording();
+            } else {
+                self.fresh_capture = true;
+                self.selected_window = 5;
+            }
         } else if self.selected_window == 5 {
             
             self.hotkey_listener();
@@ -437,15 +731,12 @@ impl eframe::App for FirstWindow {
             }
             
             
-            if self.current_os=="windows"{
-                frame.set_window_size(self.screen_size.unwrap()/(self.multiplication_factor.unwrap()));
-            }else{
-                frame.set_window_size(self.screen_size.unwrap());
-            }
+            frame.set_window_size(self.screen_size.unwrap());
            
 
             let mut paint_btn = None;
             let mut text_btn = None;
+            let mut select_btn = None;
             let mut save_btn = None;
             let mut save_edit_btn = None;
             let mut copy_btn=None;
@@ -540,6 +831,17 @@ impl eframe::App for FirstWindow {
                             self.selected_shape_string = "Select a shape!".to_string();
                             self.ready_to_cut=None;
                         }
+                        ui.vertical(
+                            |ui| {
+                                ui.add_space(6.0);
+                                select_btn = Some(ui.add(egui::Button::new(RichText::new("⬚ Select").size(20.0))).on_hover_text("Pick a shape to change its style"));
+                            }
+                        ) ;
+                       if select_btn.unwrap().clicked() {
+                            self.pp_option = Some(PpOptions::Select);
+                            self.selected_shape_string = "Select a shape!".to_string();
+                            self.ready_to_cut=None;
+                        }
                         ui.vertical(
                             |ui| {
                                 ui.add_space(6.0);
@@ -561,12 +863,73 @@ impl eframe::App for FirstWindow {
                     
                             }
                         ) ;  
+                        ui.vertical(
+                            |ui| {
+                                ui.add_space(6.0);
+                                ui.menu_button(RichText::new("⟳ Transform").size(20.0), |ui| {
+                                    let mut geometry = None;
+                                    if ui.button("⟳ Rotate 90° right").clicked() {
+                                        geometry = Some(Geometry::Rotate90);
+                                    }
+                                    if ui.button("⟲ Rotate 90° left").clicked() {
+                                        geometry = Some(Geometry::Rotate270);
+                                    }
+                                    if ui.button("Rotate 180°").clicked() {
+                                        geometry = Some(Geometry::Rotate180);
+                                    }
+                                    ui.separator();
+                                    if ui.button("⇔ Flip horizontally").clicked() {
+                                        geometry = Some(Geometry::FlipHorizontal);
+                                    }
+                                    if ui.button("⇕ Flip vertically").clicked() {
+                                        geometry = Some(Geometry::FlipVertical);
+                                    }
+                                    ui.separator();
+
+                                    let view_size = self.image_texture.as_ref().map(|t| (t.size[0] as u32, t.size[1] as u32)).unwrap_or((1, 1));
+                                    ui.horizontal(|ui| {
+                                        ui.radio_value(&mut self.resize_settings.by_percent, true, "By percent");
+                                        if ui.radio_value(&mut self.resize_settings.by_percent, false, "To size").clicked() {
+                                            (self.resize_settings.width, self.resize_settings.height) = view_size;
+                                        }
+                                    });
+                                    if self.resize_settings.by_percent {
+                                        ui.add(egui::DragValue::new(&mut self.resize_settings.percent).clamp_range(1..=1000).suffix(" %"));
+                                    } else {
+                                        ui.horizontal(|ui| {
+                                            let ratio = view_size.1 as f32 / view_size.0.max(1) as f32;
+                                            if ui.add(egui::DragValue::new(&mut self.resize_settings.width).clamp_range(1..=20000).prefix("w ")).changed() && self.resize_settings.keep_ratio {
+                                                self.resize_settings.height = ((self.resize_settings.width as f32 * ratio).round() as u32).max(1);
+                                            }
+                                            if ui.add(egui::DragValue::new(&mut self.resize_settings.height).clamp_range(1..=20000).prefix("h ")).changed() && self.resize_settings.keep_ratio {
+                                                self.resize_settings.width = ((self.resize_settings.height as f32 / ratio).round() as u32).max(1);
+                                            }
+                                        });
+                                        ui.checkbox(&mut self.resize_settings.keep_ratio, "Keep ratio");
+                                    }
+                                    //a combobox popup would close the menu
+                                    ui.horizontal(|ui| {
+                                        for filter in RESIZE_FILTERS {
+                                            ui.radio_value(&mut self.resize_settings.filter, filter, filter.label());
+                                        }
+                                    });
+                                    if ui.button("Resize").clicked() {
+                                        geometry = self.resize_geometry();
+                                    }
+
+                                    if let Some(geometry) = geometry {
+                                        self.apply_transform(ui.ctx(), geometry);
+                                        ui.close_menu();
+                                    }
+                                });
+                            }
+                        ) ;
                         }
                         
                         ui.vertical(
                             |ui| {
                                 ui.add_space(6.0);
-                                crop_btn=Some(ui.add_enabled(!self.cut_clicked && self.dim_bool,egui::Button::new(RichText::new("Cut").size(20.0))).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(5)));
+                                crop_btn=Some(ui.add_enabled(!self.cut_clicked && (self.width > 30.0 || self.height > 30.0),egui::Button::new(RichText::new("Cut").size(20.0))).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(5)));
                         
                             }
                         ) ;  
@@ -589,54 +952,6 @@ impl eframe::App for FirstWindow {
                    
                     match self.loading_state {                        
                         LoadingState::Loaded => {
-                            let dim: Vec2;
-                            if self.width >= 1200.0 && self.height >= 700.0 {
-                               //("caso 1");
-                                if self.current_os=="windows"{
-                                    self.shrink_fact=Some(0.6/self.multiplication_factor.unwrap());
-                                }else{
-                                    self.shrink_fact=Some(0.6);
-                                }
-                              
-                                dim = Vec2::new(self.width*self.shrink_fact.unwrap(), self.height*self.shrink_fact.unwrap()); 
-                            } else if self.width >= 1200.0 && self.height <= 700.0 {
-                                //("caso 2");
-                                if self.current_os=="windows"{
-                                    self.shrink_fact=Some(0.65/self.multiplication_factor.unwrap());
-                                }else{
-                                    self.shrink_fact=Some(0.65);
-                                }
-                                
-                                dim = Vec2::new(self.width*self.shrink_fact.unwrap(), self.height*self.shrink_fact.unwrap());
-                            } else if self.width <= 1200.0 && self.height >= 700.0 {   
-                               // ("caso 3");    
-                                if self.current_os=="windows"{
-                                    self.shrink_fact=Some(0.6/self.multiplication_factor.unwrap());
-                                }else{
-                                    self.shrink_fact=Some(0.6);
-                                }                                                 
-                               
-                                dim = Vec2::new(self.width*self.shrink_fact.unwrap() , self.height*self.shrink_fact.unwrap());
-                            } else {
-                               //("caso 4");
-                                if self.current_os=="windows"{
-                                    self.shrink_fact=Some(1.0/self.multiplication_factor.unwrap());
-                                }else{
-                                    self.shrink_fact=Some(1.0);
-                                }
-                            
-
-                                dim = Vec2::new(self.width * self.shrink_fact.unwrap(), self.height*self.shrink_fact.unwrap());
-                            }
-
-                            if self.shrink_fact.is_some(){
-                             if dim[0]>30.0*self.shrink_fact.unwrap() || dim[1]>30.0*self.shrink_fact.unwrap(){
-                                 self.dim_bool=true;
-                             }else{
-                                 self.dim_bool=false;
-                             } 
-                            }
-
                             let pxs ;
                             let arr;
                             let txt ;
@@ -651,8 +966,7 @@ impl eframe::App for FirstWindow {
                                 .ui(
                                     ui,
                                     egui::Image::new(self.image.as_ref().unwrap()).shrink_to_fit(),
-                                    &mut self.mult_factor,
-                                    dim,
+                                    Vec2::new(self.width, self.height),
                                     self.pp_option.clone().unwrap(),
                                     self.save,
                                     self.cut_clicked,
@@ -660,8 +974,17 @@ impl eframe::App for FirstWindow {
                                 .clone();
                                 
                                   
+                                //the shapes were merged into the image, their undo history is gone
+                                if self.save {
+                                    self.transform_undo.clear();
+                                    self.transform_redo.clear();
+                                }
                                 self.save=false;
                                 self.cropped=false;
+                                self.check_transform_history(ui.ctx());
+                                if let Some(at) = self.painting.take_color_pick() {
+                                    self.pick_color(at);
+                                }
                                 if pxs.is_none() == false {
                                                  self.line_pixels = pxs.clone().unwrap();
                                            }
@@ -701,72 +1024,21 @@ impl eframe::App for FirstWindow {
 
                             }
                             if (save_edit_btn.is_none()==false && save_edit_btn.unwrap().clicked() )|| self. ready_to_save_with_name{
-                                self.edit_image(ui);
-                                let dialog = FileDialog::new().add_filter(".jpg", &["jpg"]).add_filter(".png", &["png"]).add_filter(".gif", &["gif"]).save_file();
-                                
-                                let stringpath: String;
-                                self.save=true;
-                                self.ready_to_cut=None;
-                                
-                                if dialog.is_some(){
-                                stringpath =  dialog
-                                .clone()
-                                .unwrap()
-                                .as_os_str()
-                                .to_str()
-                                .unwrap()
-                                .to_string();
-                                 let slice = stringpath.get(..stringpath.len() -3).unwrap();
-                                self.toasts.as_mut().unwrap().success(format!(
-                                    "Image saved in {}{}",
-                                    slice,
-                                    self.image_format_string
-                                   
-                                )).set_duration(Some(Duration::from_secs(5)));
-                                
-
-                                self.show_toast=true;
-                                
-                        
-
- 
-
-  
-                                let mod_img: Option<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> = self.image_buffer.clone();
-                                if mod_img.is_none() == false {
-
-                                let slice = stringpath.get(..stringpath.len() -3).unwrap();
-
-                                    let _ = mod_img.unwrap().save(format!(
-                                        "{}{}",
-                                        slice,
-                                        self.image_format_string
-                                       
-                                    ));
-                                }
+                                self.save_img_with_name(ui);
                                 self.save = true;
+                                self.ready_to_cut=None;
                                 self.ready_to_save_with_name = false;
                                 self.pp_option=Some(PpOptions::Painting);
-                                
+
                                 self.circle_pixels= Vec::new();
                                 self.square_pixels= Vec::new();
                                 self.arrow_pixels= Vec::new();
                                 self.text_pixels= Vec::new();
                                 self.line_pixels=  Vec::new();
-  
-
-                                
-                             }
                             }
                             if (copy_btn.is_none()==false && copy_btn.unwrap().clicked()) || self.ready_to_copy{
-                                self.edit_image(ui);
-                                self.toasts.as_mut().unwrap().success("Image copied to clipboard" ).set_duration(Some(Duration::from_secs(5)));
+                                self.copy_img(ui);
                                 self.ready_to_cut=None;
-                                self.show_toast=true;
-                                let mut clipboard = Clipboard::new().unwrap();
-                                let w=self.image_buffer.clone().unwrap().width() as usize;
-                                let h=self.image_buffer.clone().unwrap().height() as usize;
-                                let _=clipboard.set_image(arboard::ImageData { width: w, height: h, bytes: self.image_buffer.clone().unwrap().as_bytes().into()});
                                 self.ready_to_copy=false;
                             }
 
@@ -779,12 +1051,12 @@ impl eframe::App for FirstWindow {
             .default_width(300.0)
             .default_height(50.0)
             .title_bar(false)
-            .default_pos(Pos2::new((frame.info().window_info.size.x/2.0)/self.multiplication_factor.unwrap(), (frame.info().window_info.size.y/2.0)/self.multiplication_factor.unwrap()))
+            .default_pos((frame.info().window_info.size / 2.0).to_pos2())
             .vscroll(false)
             .interactable(true)
             .resizable(true)
             .show(ctx, |ui| {
-                ui.label(RichText::new("Your changes will be saved, do you want to proceed?").color(Color32::WHITE).size(15.0));
+                ui.label(RichText::new("Your drawings will be merged into the image before cropping, do you want to proceed?").color(Color32::WHITE).size(15.0));
                 let yes_btn=Some(ui.add(egui::Button::new("Yes")));
                 let no_btn=Some(ui.add(egui::Button::new("No")));
 
@@ -803,12 +1075,6 @@ impl eframe::App for FirstWindow {
                            
                             if (crop_btn.unwrap().clicked() || self.cut_clicked==true)||self.ready_to_crop{   
 
-                                ui.horizontal(|ui|{
-                                    finish_crop=Some(ui.add_enabled(self.cut_clicked, egui::Button::new(RichText::new("Finish Your Cut").size(20.0))));
-                                exit_cut_btn=Some(ui.add_enabled(self.cut_clicked, egui::Button::new(RichText::new("Exit").size(20.0))));
-                                  
-                                });
-                                                       
                                 if self.ready_to_cut.is_none() && (self.square_pixels.len()>0 || self.text_pixels.len()>0 || self.circle_pixels.len()>0 || self.line_pixels.len()>0 || self.arrow_pixels.len()>0){
                                     self.ready_to_cut=Some(false);
                                 }
@@ -816,114 +1082,72 @@ impl eframe::App for FirstWindow {
                                 
                                 if (self.ready_to_cut.is_none()==false && self.ready_to_cut.unwrap()==true) || (self.square_pixels.len()==0 && self.text_pixels.len()==0 && self.circle_pixels.len()==0 && self.line_pixels.len()==0 && self.arrow_pixels.len()==0) {
                                      
-                                
+                                let entering = !self.cut_clicked;
                                 self.pp_option = Some(PpOptions::Cut);
                                 self.cut_clicked=true;
                                 
-                                if self.arrow_pixels.len()>0
-                                    || self.circle_pixels.len()>0
-                                    || self.square_pixels.len()>0
-                                    || self.text_pixels.len()>0
-                                    || self.line_pixels.len()>0{
-                                        self.edit_image(ui);
-                                        self.circle_pixels= Vec::new();
-                                        self.square_pixels= Vec::new();
-                                        self.arrow_pixels= Vec::new();
-                                        self.text_pixels= Vec::new();
-                                        self.line_pixels=  Vec::new();
-     
-                                    }
-                                
-                                let mut pos_bug_fixed=Pos2::new(0.0,0.0);
-
-                                if ui.input(|i| i.pointer.hover_pos().is_none()==false){
-                                    
-                                    ui.input(|i| 
-                                        pos_bug_fixed=i.pointer.hover_pos().unwrap()
-                                        );
+                                if entering {
+                                    if self.arrow_pixels.len()>0
+                                        || self.circle_pixels.len()>0
+                                        || self.square_pixels.len()>0
+                                        || self.text_pixels.len()>0
+                                        || self.line_pixels.len()>0{
+                                            self.edit_image(ui);
+                                            self.circle_pixels= Vec::new();
+                                            self.square_pixels= Vec::new();
+                                            self.arrow_pixels= Vec::new();
+                                            self.text_pixels= Vec::new();
+                                            self.line_pixels=  Vec::new();
+         
+                                        }
+                                    self.begin_crop(ui.ctx());
                                 }
 
-                                
-                                if   pos_bug_fixed.x<=response.clone().unwrap().rect.right_top().x &&
-                                     pos_bug_fixed.x>=response.clone().unwrap().rect.left_top().x &&
-                                     pos_bug_fixed.y>=response.clone().unwrap().rect.left_top().y &&
-                                     pos_bug_fixed.y<=response.clone().unwrap().rect.right_bottom().y {
-                                        self.is_pointer_on_cut_window = true;
-                                     }
-                                     else {
-                                        self.is_pointer_on_cut_window = false;
-                                     }
-                                
-                            
-                               
-                                
-                                egui::Window::new("cut")
-                               
-                                .constraint_to(response.clone().unwrap().rect)
-                                .min_width(30.0*self.shrink_fact.unwrap())
-                                .min_height(30.0*self.shrink_fact.unwrap())
-                                .default_width(dim[0]-1.0)
-                                .default_height(dim[1]-1.0)
-                                .title_bar(false)
-                                .default_pos(Pos2::new(response.clone().unwrap().rect.left_top().x+1.0, response.clone().unwrap().rect.left_top().y+1.0))
-                                .vscroll(false)
-                                .interactable(   self.is_pointer_on_cut_window )
-                                .resizable(   self.is_pointer_on_cut_window )
-                                .frame(egui::Frame::none()
-                                     .fill(egui::Color32::from_rgba_unmultiplied(70, 70, 70, 70))
-                                     .stroke(Stroke::new(1.0, egui::Color32::WHITE))
-                                     )
-                                .show(ctx, |ui| {
-                             
-                                     if self.set_wh_window{
-                                      
-                                        ui.set_height(dim[1]-1.0);
-                                        ui.set_width(dim[0]-1.0);
-                                        
-                                        self.set_wh_window=false;
-                                     }
-                               
-                                   
-                                     ui.painter().add(
-                                        egui::Shape::dashed_line(
-                                        &[
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x+(ui.available_rect_before_wrap().right_bottom().x-ui.available_rect_before_wrap().left_top().x)*0.33, ui.available_rect_before_wrap().left_top().y),
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x+(ui.available_rect_before_wrap().right_bottom().x-ui.available_rect_before_wrap().left_top().x)*0.33, ui.available_rect_before_wrap().right_bottom().y)],
-                                        Stroke::new(2.0, Color32::WHITE),
-                                        10.0, 5.0));
-
-                                    ui.painter().add(
-                                        egui::Shape::dashed_line(
-                                        &[
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x+(ui.available_rect_before_wrap().right_bottom().x-ui.available_rect_before_wrap().left_top().x)*0.66, ui.available_rect_before_wrap().left_top().y),
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x+(ui.available_rect_before_wrap().right_bottom().x-ui.available_rect_before_wrap().left_top().x)*0.66, ui.available_rect_before_wrap().right_bottom().y)],
-                                        Stroke::new(2.0, Color32::WHITE),
-                                        10.0, 5.0));
-
-                                 
-                                    ui.painter().add(
-                                        egui::Shape::dashed_line(
-                                        &[
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x,ui.available_rect_before_wrap().left_top().y+(ui.available_rect_before_wrap().right_bottom().y-ui.available_rect_before_wrap().left_top().y)*0.33),
-                                            Pos2::new(ui.available_rect_before_wrap().right_bottom().x,ui.available_rect_before_wrap().left_top().y+(ui.available_rect_before_wrap().right_bottom().y-ui.available_rect_before_wrap().left_top().y)*0.33)],
-                                        Stroke::new(2.0, Color32::WHITE),
-                                        10.0, 5.0));
-
-                                    ui.painter().add(
-                                        egui::Shape::dashed_line(
-                                        &[
-                                            Pos2::new(ui.available_rect_before_wrap().left_top().x,ui.available_rect_before_wrap().left_top().y+(ui.available_rect_before_wrap().right_bottom().y-ui.available_rect_before_wrap().left_top().y)*0.66),
-                                            Pos2::new(ui.available_rect_before_wrap().right_bottom().x,ui.available_rect_before_wrap().left_top().y+(ui.available_rect_before_wrap().right_bottom().y-ui.available_rect_before_wrap().left_top().y)*0.66)],
-                                        Stroke::new(2.0, Color32::WHITE),
-                                        10.0, 5.0));
-                                    self.to_cut_rect= Some((ui.available_rect_before_wrap().left_top(), ui.available_rect_before_wrap().right_bottom()));
-                                    
-                                    ui.allocate_space(ui.available_size());
-                                    
-                                });
- 
+                                //in the first frame the canvas still shows the cropped view,
+                                //the whole image is there from the next one
+                                let canvas = response.clone().unwrap().rect;
+                                let mut confirmed = false;
+                                if !entering {
+                                    confirmed = self.crop_ui(ui, canvas);
+
+                                    ui.horizontal(|ui|{
+                                        finish_crop=Some(ui.add(egui::Button::new(RichText::new("Apply crop").size(20.0))).on_hover_text("Enter"));
+                                        exit_cut_btn=Some(ui.add(egui::Button::new(RichText::new("Exit").size(20.0))));
+                                        if ui.add(egui::Button::new(RichText::new("Reset").size(20.0))).clicked() {
+                                            if let Some(selection) = self.crop_selection.as_mut() {
+                                                selection.set_rect(canvas, canvas);
+                                            }
+                                        }
+                                        ui.separator();
+                                        egui::ComboBox::from_id_source("crop_preset_Combobox")
+                                            .selected_text(self.crop_preset.label())
+                                            .show_ui(ui, |ui| {
+                                                for preset in PRESETS {
+                                                    if ui.selectable_label(self.crop_preset == preset, preset.label()).clicked() {
+                                                        self.set_crop_preset(preset, canvas);
+                                                    }
+                                                }
+                                            });
+                                        let (img_w, img_h) = self.image_buffer.as_ref().unwrap().dimensions();
+                                        if let Some((mut x, mut y, mut w, mut h)) = self.crop_pixels(canvas) {
+                                            let mut changed = false;
+                                            ui.label("x");
+                                            changed |= ui.add(egui::DragValue::new(&mut x).clamp_range(0..=img_w - 1)).changed();
+                                            ui.label("y");
+                                            changed |= ui.add(egui::DragValue::new(&mut y).clamp_range(0..=img_h - 1)).changed();
+                                            ui.label("w");
+                                            changed |= ui.add(egui::DragValue::new(&mut w).clamp_range(1..=img_w)).changed();
+                                            ui.label("h");
+                                            changed |= ui.add(egui::DragValue::new(&mut h).clamp_range(1..=img_h)).changed();
+                                            if changed {
+                                                self.set_crop_pixels((x, y, w, h), canvas);
+                                            }
+                                        }
+                                    });
+                                }
                             
-                                if exit_cut_btn.unwrap().clicked(){
+                                if exit_cut_btn.is_some_and(|b| b.clicked()){
+                                    self.cancel_crop(ui.ctx());
                                     self.cut_clicked=false;
                                     self.pp_option=Some(PpOptions::Painting);
                                     self.ready_to_crop= false;
@@ -932,11 +1156,10 @@ impl eframe::App for FirstWindow {
                                     self.cropped=false;
                                 }
 
-                                if finish_crop.unwrap().clicked(){
+                                if confirmed || finish_crop.is_some_and(|b| b.clicked()){
                                    
-                                    self.set_wh_window=true;
+                                    self.apply_crop(ui.ctx(), canvas);
                                     self.cut_clicked=false;
-                                    self.load_cutted_img(ui, response);
                                     self.cropped=true;
                                     self.pp_option=Some(PpOptions::Painting);
                                     self.ready_to_crop= false;
@@ -947,7 +1170,7 @@ impl eframe::App for FirstWindow {
                                
 
                             }
-                            }else if dim[0]==30.0 && dim[1]==30.0{
+                            }else if self.width <= 30.0 && self.height <= 30.0{
                                 self.cut_clicked=false;
                             }
                             if settings_btn.is_none()==false && settings_btn.unwrap().clicked(){
@@ -955,11 +1178,15 @@ impl eframe::App for FirstWindow {
                             }
                         }
                         LoadingState::NotLoaded => {
-                            
+                                //images reopened from the history are not captures
+                                let fresh_capture = std::mem::take(&mut self.fresh_capture);
                                 if self.image_texture.is_none()==false && (self.width>50.0 && self.height>50.0){
                                     self.load_image(ui);
                                     self.pp_option = Some(PpOptions::Painting);
                                     self.loading_state = LoadingState::Loaded;
+                                    if fresh_capture {
+                                        self.run_after_capture_actions(ui);
+                                    }
 
                                 }else{
                                     self.selected_window=1;
@@ -978,172 +1205,469 @@ impl eframe::App for FirstWindow {
                 self.toasts.as_mut().unwrap().show(ctx);    
             }
            
-            let screens=Screen::all().unwrap();
             egui::CentralPanel::default().show(ctx, |ui| {
-                if ui.button("Choose Path").clicked() {
-                    self.filepath = FileDialog::new()
-                        .set_directory("./screenshot")
-                        .pick_folder();
-                }
-                ui.add_space(10.0);
-                ui.heading(RichText::new("Select a format").color(Color32::WHITE));
-                if ui
-                    .add(egui::RadioButton::new(
-                        self.image_format == Some(ImageFormat::Jpg),
-                        "jpg",
-                    ))
-                    .clicked()
-                {
-                    self.image_format = Some(ImageFormat::Jpg);
-                    self.image_format_string = "jpg".to_string();
-                }
-                if ui
-                    .add(egui::RadioButton::new(
-                        self.image_format == Some(ImageFormat::Png),
-                        "png",
-                    ))
-                    .clicked()
-                {
-                    self.image_format = Some(ImageFormat::Png);
-                    self.image_format_string = "png".to_string();
-                }
-                if ui
-                    .add(egui::RadioButton::new(
-                        self.image_format == Some(ImageFormat::Gif),
-                        "gif",
-                    ))
-                    .clicked()
-                {
-                    self.image_format = Some(ImageFormat::Gif);
-                    self.image_format_string = "gif".to_string();
-                }
-                ui.add_space(10.0);
-                ui.heading(RichText::new("Select a monitor").color(Color32::WHITE));
-                if ui
-                    .add(egui::RadioButton::new(
-                        self.screen_to_show==Some(screens[0].display_info.id),
-                        "Primary",
-                    ))
-                    .clicked()
-                {
-                    self.screen_to_show=Some(screens[0].display_info.id);
-                    self.screen_size=Some(Vec2::new(screens[0].display_info.width as f32, screens[0].display_info.height as f32));
-                    self.frame_initial_pos=Some(Pos2::new(screens[0].display_info.x as f32, screens[0].display_info.y as f32));
-                    
-                }
-                if screens.len()==2{
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if ui.button("Choose Path").clicked() {
+                        self.filepath = FileDialog::new()
+                            .set_directory("./screenshot")
+                            .pick_folder();
+                    }
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("Select a format").color(Color32::WHITE));
                     if ui
-                    .add(egui::RadioButton::new(
-                        self.screen_to_show==Some(screens[1].display_info.id),
-                        "Secondary",
-                    ))
-                    .clicked()
-                {
-                    self.screen_to_show=Some(screens[1].display_info.id);
-                    self.screen_size=Some(Vec2::new(screens[1].display_info.width as f32, screens[1].display_info.height as f32));
-                    self.frame_initial_pos=Some(Pos2::new(screens[1].display_info.x as f32, screens[1].display_info.y as f32));
-                   
-                }
-                }
-               
-                ui.add_space(10.0);
-                egui::Grid::new("my_grid")
-                .num_columns(2)
-                .spacing([40.0, 4.0])
-                .striped(true)
-                .show(ui, |ui| {
-                    ui.heading(RichText::new("Customizable Shortcuts").color(Color32::WHITE));
+                        .add(egui::RadioButton::new(
+                            self.image_format == Some(ImageFormat::Jpg),
+                            "jpg",
+                        ))
+                        .clicked()
+                    {
+                        self.image_format = Some(ImageFormat::Jpg);
+                        self.image_format_string = "jpg".to_string();
+                    }
+                    if ui
+                        .add(egui::RadioButton::new(
+                            self.image_format == Some(ImageFormat::Png),
+                            "png",
+                        ))
+                        .clicked()
+                    {
+                        self.image_format = Some(ImageFormat::Png);
+                        self.image_format_string = "png".to_string();
+                    }
+                    if ui
+                        .add(egui::RadioButton::new(
+                            self.image_format == Some(ImageFormat::Gif),
+                            "gif",
+                        ))
+                        .clicked()
+                    {
+                        self.image_format = Some(ImageFormat::Gif);
+                        self.image_format_string = "gif".to_string();
+                    }
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("File naming").color(Color32::WHITE));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.naming_template).desired_width(300.0));
+                        if ui.button("Reset").clicked() {
+                            self.naming_template = naming::DEFAULT_TEMPLATE.to_string();
+                        }
+                    });
+                    let example = naming::NamingContext::new(
+                        self.capture_counter + 1,
+                        self.monitor_index(),
+                        self.mode_tag(),
+                        self.screen_size.unwrap().x as u32,
+                        self.screen_size.unwrap().y as u32,
+                    );
+                    ui.label(RichText::new(format!("Example: {}.{}", naming::render_template(&self.naming_template, &example), self.image_format_string)).color(Color32::GRAY));
+                    ui.label(RichText::new(format!("Tokens: {} - use / to create subfolders", naming::TOKENS_HELP)).size(12.0));
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("After capture").color(Color32::WHITE));
+                    ui.checkbox(&mut self.open_editor_after_capture, "Open the editor");
+                    ui.checkbox(&mut self.auto_save_after_capture, "Save to the default folder");
+                    ui.checkbox(&mut self.auto_copy_after_capture, "Copy to clipboard");
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("Selection").color(Color32::WHITE));
+                    ui.checkbox(&mut self.freeze_screen, "Freeze the screen while selecting a rectangle");
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("Cursor").color(Color32::WHITE));
+                    ui.checkbox(&mut self.capture_cursor, "Include the mouse cursor in the captures");
+                    ui.add_enabled(self.capture_cursor, egui::Checkbox::new(&mut self.cursor_ring, "Highlight clicks with a ring"));
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("Beautify").color(Color32::WHITE));
+                    ui.checkbox(&mut self.beautify.enabled, "Frame the images saved and copied from the editor");
+                    ui.add_enabled_ui(self.beautify.enabled, |ui| {
+                        egui::Grid::new("beautify_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Padding");
+                            ui.add(egui::DragValue::new(&mut self.beautify.padding).clamp_range(0..=500).suffix(" px"));
+                            ui.end_row();
+                            ui.label("Background");
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_srgba(&mut self.beautify.background);
+                                ui.checkbox(&mut self.beautify.gradient, "Gradient to");
+                                if self.beautify.gradient {
+                                    ui.color_edit_button_srgba(&mut self.beautify.background_end);
+                                }
+                            });
+                            ui.end_row();
+                            ui.label("Rounded corners");
+                            ui.add(egui::DragValue::new(&mut self.beautify.corner_radius).clamp_range(0..=200).suffix(" px"));
+                            ui.end_row();
+                            ui.label("Border");
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut self.beautify.border_width).clamp_range(0..=100).suffix(" px"));
+                                ui.color_edit_button_srgba(&mut self.beautify.border_color);
+                            });
+                            ui.end_row();
+                            ui.label("Shadow");
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut self.beautify.shadow_opacity).prefix("opacity "));
+                                ui.add(egui::DragValue::new(&mut self.beautify.shadow_blur).clamp_range(0..=100).prefix("blur ").suffix(" px"));
+                                ui.add(egui::DragValue::new(&mut self.beautify.shadow_offset).clamp_range(0..=100).prefix("offset ").suffix(" px"));
+                            });
+                            ui.end_row();
+                        });
+                        ui.horizontal(|ui| {
+                            let presets = self.beautify_presets.get_presets().to_vec();
+                            egui::ComboBox::from_id_source("beautify_preset_Combobox")
+                                .selected_text("Presets")
+                                .show_ui(ui, |ui| {
+                                    for (name, preset) in presets {
+                                        if ui.selectable_label(false, &name).clicked() {
+                                            self.beautify = preset;
+                                            self.beautify_preset_name = name;
+                                        }
+                                    }
+                                });
+                            ui.add(egui::TextEdit::singleline(&mut self.beautify_preset_name).hint_text("Preset name").desired_width(150.0));
+                            let name = self.beautify_preset_name.trim().to_string();
+                            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save preset")).clicked() {
+                                if let Err(e) = self.beautify_presets.add(&name, self.beautify) {
+                                    self.toasts.as_mut().unwrap().error(format!("Unable to save the preset: {}", e)).set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast = true;
+                                }
+                            }
+                            let exists = self.beautify_presets.get_presets().iter().any(|(n, _)| *n == name);
+                            if ui.add_enabled(exists, egui::Button::new("Delete preset")).clicked()
+                                && self.beautify_presets.remove(&name).is_err()
+                            {
+                                self.toasts.as_mut().unwrap().error("Unable to delete the preset").set_duration(Some(Duration::from_secs(5)));
+                                self.show_toast = true;
+                            }
+                        });
+                    });
+                    ui.add_space(10.0);
+                    ui.heading(RichText::new("Select a monitor").color(Color32::WHITE));
+                    let monitors = monitors::all_monitors();
+                    let (layout_response, painter) = ui.allocate_painter(Vec2::new(400.0, 150.0), egui::Sense::click());
+                    for (i, (monitor, rect)) in monitors::fit_layout(&monitors, layout_response.rect.shrink(4.0)).into_iter().enumerate() {
+                        let selected = self.screen_to_show == Some(monitor.id);
+                        let hovered = layout_response.hover_pos().is_some_and(|p| rect.contains(p));
+                        let fill = if selected {
+                            Color32::from_rgb(40, 110, 180)
+                        } else if hovered {
+                            Color32::from_gray(90)
+                        } else {
+                            Color32::from_gray(60)
+                        };
+                        painter.rect(rect.shrink(2.0), 4.0, fill, Stroke::new(1.0, Color32::WHITE));
+                        painter.text(rect.center(), egui::Align2::CENTER_CENTER, (i + 1).to_string(), egui::FontId::proportional(20.0), Color32::WHITE);
+                        if layout_response.clicked() && hovered {
+                            self.select_monitor(&monitor);
+                        }
+                    }
+                    for (i, monitor) in monitors.iter().enumerate() {
+                        let label = format!(
+                            "{} - {}x{} at ({}, {}){}",
+                            i + 1,
+                            monitor.width,
+                            monitor.height,
+                            monitor.x,
+                            monitor.y,
+                            if monitor.is_primary { " - Primary" } else { "" }
+                        );
+                        if ui
+                            .add(egui::RadioButton::new(self.screen_to_show==Some(monitor.id), label))
+                            .clicked()
+                        {
+                            self.select_monitor(monitor);
+                        }
+                    }
+                    ui.add_space(10.0);
+                    egui::Grid::new("my_grid")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.heading(RichText::new("Customizable Shortcuts").color(Color32::WHITE));
                     
 
 
-                    ui.end_row();
-                             if ui.button("Exit button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                        ui.end_row();
+                                 if ui.button("Exit button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=0;      
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(0)).color(Color32::GRAY));
-                            ui.end_row();  
+                                    self.customizing_hotkey=0;      
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(0)).color(Color32::GRAY));
+                                ui.end_row();  
 
-                            if ui.button("Screenshot button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                                if ui.button("Screenshot button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=1;
+                                    self.customizing_hotkey=1;
                                 
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(1)).color(Color32::GRAY));
-                            ui.end_row();
-                            if ui.button("Save button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(1)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Save button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=2;                                
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(2)).color(Color32::GRAY));
-                            ui.end_row();
-                            if ui.button("Save with Name button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                                    self.customizing_hotkey=2;                                
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(2)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Save with Name button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=3;
+                                    self.customizing_hotkey=3;
                                 
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(3)).color(Color32::GRAY));
-                            ui.end_row();
-                            if ui.button("Copy button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(3)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Copy button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=4;
+                                    self.customizing_hotkey=4;
                                 
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(4)).color(Color32::GRAY));
-                            ui.end_row();
-                            if ui.button("Crop button").clicked(){
-                                self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
-                                self.show_toast=true;
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(4)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Crop button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
                              
-                                self.customizing_hotkey=5;
+                                    self.customizing_hotkey=5;
                                 
-                            }
-                            ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(5)).color(Color32::GRAY));
-                            ui.end_row();
-                            
-                            if self.customizing_hotkey != usize::MAX{
-                                ui.ctx().output_mut(|i| i.cursor_icon = CursorIcon::Wait);
-                                 let ret=self.customize_shortcut(ui);
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(5)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Last region button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
+                             
+                                    self.customizing_hotkey=6;
                                 
-                                if ret==1{
-                                    self.toasts.as_mut().unwrap().success("Shortcut changed!" ).set_duration(Some(Duration::from_secs(5))); 
-                                     self.show_toast=true;            
-                                }else if ret==2{                                    
-                                    self.toasts.as_mut().unwrap().error("Shortcut already used or invalid!" ).set_duration(Some(Duration::from_secs(5)));
-                                    self.show_toast=true; 
-                                }else if ret==3{
-                                    
-                                    self.toasts.as_mut().unwrap().error("Too many digits for the shortcut").set_duration(Some(Duration::from_secs(5)));
+                                }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(6)).color(Color32::GRAY));
+                                ui.end_row();
+                                if ui.button("Stop recording button").clicked(){
+                                    self.toasts.as_mut().unwrap().info("Waiting for the keyboard input").set_duration(Some(Duration::from_secs(5)));
                                     self.show_toast=true;
-                                    self.customizing_hotkey=usize::MAX;
+                             
+                                    self.customizing_hotkey=7;
+                                
                                 }
+                                ui.label(RichText::new(self.shortcuts.get_hotkey_strings_formatted(7)).color(Color32::GRAY));
+                                ui.end_row();
+                            
+                                if self.customizing_hotkey != usize::MAX{
+                                    ui.ctx().output_mut(|i| i.cursor_icon = CursorIcon::Wait);
+                                     let ret=self.customize_shortcut(ui);
                                 
-                            }
+                                    if ret==1{
+                                        self.toasts.as_mut().unwrap().success("Shortcut changed!" ).set_duration(Some(Duration::from_secs(5))); 
+                                         self.show_toast=true;            
+                                    }else if ret==2{                                    
+                                        self.toasts.as_mut().unwrap().error("Shortcut already used or invalid!" ).set_duration(Some(Duration::from_secs(5)));
+                                        self.show_toast=true; 
+                                    }else if ret==3{
+                                    
+                                        self.toasts.as_mut().unwrap().error("Too many digits for the shortcut").set_duration(Some(Duration::from_secs(5)));
+                                        self.show_toast=true;
+                                        self.customizing_hotkey=usize::MAX;
+                                    }
+                                
+                                }
                            
 
-                });
+                    });
                 
-                ui.label(RichText::new("First click on the shortcut to modify and then press a combination of CTRL, ALT or SHIFT and a letter").size(15.0));
+                    ui.label(RichText::new("First click on the shortcut to modify and then press a combination of CTRL, ALT or SHIFT and a letter").size(15.0));
               
-                ui.add_space(20.0);
-                if ui.button("Back").clicked() {
-                    if self.image.is_none(){
+                    ui.add_space(20.0);
+                    if ui.button("Back").clicked() {
+                        if self.image.is_none(){
+                            self.selected_window = 1;
+                        }else if !self.image.is_none(){
+                            self.selected_window=5;
+                        }
+                    
+                    }
+                });
+            });
+        } else if self.selected_window == 7 {
+            self.hotkey_listener();
+            frame.set_decorations(true);
+            frame.set_window_size(egui::vec2(680.0, 480.0));
+
+            if self.show_toast{
+                self.toasts.as_mut().unwrap().show(ctx);
+            }
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Back").clicked() {
                         self.selected_window = 1;
-                    }else if !self.image.is_none(){
-                        self.selected_window=5;
                     }
-                    
+                    ui.heading(RichText::new("Capture history").color(Color32::WHITE));
+                });
+                ui.add_space(10.0);
+
+                let entries = self.history.get_entries().to_vec();
+                if entries.is_empty() {
+                    ui.label(RichText::new("Saved captures will be listed here").size(20.0).color(Color32::GRAY));
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("history_grid")
+                    .num_columns(3)
+                    .spacing([20.0, 10.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for entry in entries {
+                            match self.history_thumbnail(ctx, &entry.path) {
+                                Some(thumbnail) => {
+                                    ui.add(egui::Image::new(&thumbnail));
+                                }
+                                None => {
+                                    ui.label(RichText::new("File not found").color(Color32::GRAY));
+                                }
+                            }
+
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string()).color(Color32::WHITE));
+                                ui.label(format!("{} x {}", entry.width, entry.height));
+                                ui.label(RichText::new(entry.path.display().to_string()).size(12.0).color(Color32::GRAY));
+                            });
+
+                            ui.vertical(|ui| {
+                                if ui.button("Open in editor").clicked() {
+                                    if let Some(img) = self.open_history_entry(&entry.path) {
+                                        self.open_in_editor(img);
+                                    }
+                                }
+                                if ui.button("Copy").clicked() {
+                                    if let Some(img) = self.open_history_entry(&entry.path) {
+                                        self.copy_to_clipboard(&img);
+                                    }
+                                }
+                                if ui.button("Reveal").clicked() && history::reveal_in_file_manager(&entry.path).is_err() {
+                                    self.toasts.as_mut().unwrap().error(format!("Unable to open the folder of {}", entry.path.display())).set_duration(Some(Duration::from_secs(5)));
+                                    self.show_toast=true;
+                                }
+                                if ui.button("Delete").clicked() {
+                                    self.delete_history_entry(&entry.path);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        } else if self.selected_window == 12 {
+            self.hotkey_listener();
+            let (due, over, until_next) = match &self.interval_session {
+                Some(session) => (session.is_due(), session.is_over(), session.until_next()),
+                None => (false, true, Duration::ZERO),
+            };
+            if over {
+                self.stop_interval_capture();
+            } else if due {
+                match self.capture_again() {
+                    Some(shot) => self.save_interval_frame(shot),
+                    None => {
+                        self.toasts.as_mut().unwrap().error("Interval capture stopped: unable to capture the screen").set_duration(Some(Duration::from_secs(5)));
+                        self.stop_interval_capture();
+                    }
+                }
+            } else if until_next <= INTERVAL_HIDE_BEFORE_SHOT {
+                //the panel must not end up in the next frame
+                frame.set_window_size(Vec2::new(0.0, 0.0));
+            } else {
+                frame.set_decorations(true);
+                frame.set_window_size(functions::first_window::INTERVAL_PANEL_SIZE);
+                frame.set_window_pos(self.interval_panel_pos);
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let Some(session) = &self.interval_session {
+                        ui.label(RichText::new(format!("Frames saved: {}  skipped: {}", session.saved, session.skipped)).size(20.0));
+                        ui.label(format!("Next frame in {} s", until_next.as_secs_f32().ceil()));
+                        if let Some(remaining) = session.remaining() {
+                            ui.label(format!("Stops in {} min {} s", remaining.as_secs() / 60, remaining.as_secs() % 60));
+                        }
+                    }
+                    ui.add_space(10.0);
+                    if ui.button(RichText::new("Stop").size(20.0)).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(0)).clicked() {
+                        self.stop_interval_capture();
+                    }
+                });
+            }
+        } else if self.selected_window == 13 {
+            self.hotkey_listener();
+            let (due, over) = match &self.recording {
+                Some(recording) => (recording.is_due(), recording.is_over()),
+                None => (false, true),
+            };
+            if over {
+                self.stop_recording();
+            } else if due {
+                self.record_frame();
+            }
+
+            match self.recording_panel_pos {
+                Some(pos) if self.selected_window == 13 => {
+                    frame.set_decorations(false);
+                    frame.set_window_size(functions::first_window::RECORDING_PANEL_SIZE);
+                    frame.set_window_pos(pos);
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        if let Some(recording) = &self.recording {
+                            let elapsed = recording.elapsed().as_secs();
+                            ui.label(RichText::new(format!("● REC {:02}:{:02}", elapsed / 60, elapsed % 60)).size(20.0).color(Color32::RED));
+                            ui.label(format!("{} frames", recording.frame_count()));
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button(RichText::new("Stop").size(20.0)).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(7)).clicked() {
+                                self.stop_recording();
+                            }
+                            if ui.button(RichText::new("Cancel").size(20.0)).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(0)).clicked() {
+                                self.cancel_recording();
+                            }
+                        });
+                    });
+                }
+                None if self.selected_window == 13 => {
+                    frame.set_decorations(false);
+                    frame.set_window_size(functions::first_window::RECORDING_INDICATOR_SIZE);
+                    frame.set_window_pos(self.recording_indicator_pos);
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        if let Some(recording) = &self.recording {
+                            let elapsed = recording.elapsed().as_secs();
+                            ui.label(RichText::new(format!("● REC {:02}:{:02}", elapsed / 60, elapsed % 60)).size(16.0).color(Color32::RED));
+                        }
+                        ui.label(format!("{} to stop, {} to cancel", self.shortcuts.get_hotkey_strings_formatted(7), self.shortcuts.get_hotkey_strings_formatted(0)));
+                    });
                 }
+                //stopped during this frame
+                _ => {}
+            }
+            frame.set_always_on_top(self.selected_window == 13);
+        } else if self.selected_window == 8 {
+            self.hotkey_listener();
+            frame.set_decorations(true);
+            frame.set_window_size(functions::first_window::SCROLL_PANEL_SIZE);
+            frame.set_window_pos(self.scroll_panel_pos);
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label(RichText::new(format!("Scroll the content, then click Add frame or press {}", self.shortcuts.get_hotkey_strings_formatted(1))).color(Color32::WHITE));
+                ui.label(RichText::new(format!("Frames captured: {}", self.scroll_frames.len())).size(20.0));
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new("Add frame").size(20.0)).clicked() {
+                        self.add_scroll_frame();
+                    }
+                    if ui.button(RichText::new("Done").size(20.0)).clicked() {
+                        self.finish_scrolling_capture();
+                    }
+                    if ui.button(RichText::new("Cancel").size(20.0)).on_hover_text(self.shortcuts.get_hotkey_strings_formatted(0)).clicked() {
+                        self.cancel_scrolling_capture();
+                    }
+                });
             });
         }
 
@@ -0,0 +1,283 @@
+use display_info::DisplayInfo;
+use egui::{Pos2, Rect, Vec2};
+use image::{imageops, RgbaImage};
+
+use crate::coords::ImageMapping;
+
+//A display in the virtual desktop, position and size are in logical units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+impl Monitor {
+    pub fn from_display_info(info: &DisplayInfo) -> Self {
+        let scale_factor = if info.scale_factor > 0.0 {
+            info.scale_factor
+        } else {
+            1.0
+        };
+        Monitor {
+            id: info.id,
+            x: info.x,
+            y: info.y,
+            width: info.width,
+            height: info.height,
+            scale_factor,
+            is_primary: info.is_primary,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_min_size(
+            Pos2::new(self.x as f32, self.y as f32),
+            Vec2::new(self.width as f32, self.height as f32),
+        )
+    }
+}
+
+pub fn all_monitors() -> Vec<Monitor> {
+    DisplayInfo::all()
+        .unwrap_or_default()
+        .iter()
+        .map(Monitor::from_display_info)
+        .collect()
+}
+
+//The primary monitor, or the first one when none is marked as primary
+pub fn primary(monitors: &[Monitor]) -> Option<Monitor> {
+    monitors
+        .iter()
+        .find(|m| m.is_primary)
+        .or(monitors.first())
+        .copied()
+}
+
+//The monitor showing `p` (logical units)
+pub fn monitor_at(monitors: &[Monitor], p: Pos2) -> Option<Monitor> {
+    monitors.iter().find(|m| m.rect().contains(p)).copied()
+}
+
+//Scales the whole layout to fit inside `area` keeping the proportions,
+//giving back where every monitor has to be drawn
+pub fn fit_layout(monitors: &[Monitor], area: Rect) -> Vec<(Monitor, Rect)> {
+    let (min_x, min_y, width, height) = match virtual_bounds(monitors) {
+        Some(bounds) if bounds.2 > 0 && bounds.3 > 0 => bounds,
+        _ => return Vec::new(),
+    };
+    let scale = (area.width() / width as f32).min(area.height() / height as f32);
+    let offset = area.center() - Vec2::new(width as f32, height as f32) * scale / 2.0;
+
+    monitors
+        .iter()
+        .map(|m| {
+            let min = offset + Vec2::new((m.x - min_x) as f32, (m.y - min_y) as f32) * scale;
+            let size = Vec2::new(m.width as f32, m.height as f32) * scale;
+            (*m, Rect::from_min_size(min, size))
+        })
+        .collect()
+}
+
+//Smallest logical rectangle (x, y, width, height) containing every monitor
+pub fn virtual_bounds(monitors: &[Monitor]) -> Option<(i32, i32, u32, u32)> {
+    let min_x = monitors.iter().map(|m| m.x).min()?;
+    let min_y = monitors.iter().map(|m| m.y).min()?;
+    let max_x = monitors.iter().map(|m| m.x + m.width as i32).max()?;
+    let max_y = monitors.iter().map(|m| m.y + m.height as i32).max()?;
+    Some((min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32))
+}
+
+//Puts the capture of every monitor where the monitor is in the virtual desktop,
+//areas not covered by any monitor stay transparent
+pub fn compose_virtual_desktop(captures: &[(Monitor, RgbaImage)]) -> Option<RgbaImage> {
+    let monitors: Vec<Monitor> = captures.iter().map(|(m, _)| *m).collect();
+    let (x, y, width, height) = virtual_bounds(&monitors)?;
+    let area = Rect::from_min_size(
+        Pos2::new(x as f32, y as f32),
+        Vec2::new(width as f32, height as f32),
+    );
+    let pieces: Vec<(Rect, f32, RgbaImage)> = captures
+        .iter()
+        .map(|(m, img)| {
+            let scale = ImageMapping::new(m.rect(), img.width(), img.height()).scale().x;
+            (m.rect(), scale, img.clone())
+        })
+        .collect();
+    compose_area(area, &pieces)
+}
+
+//Parts of `area` (virtual desktop coordinates) shown by every monitor,
+//each one relative to the top-left corner of its monitor
+pub fn split_by_monitor(monitors: &[Monitor], area: Rect) -> Vec<(Monitor, Rect)> {
+    monitors
+        .iter()
+        .filter_map(|m| {
+            let part = m.rect().intersect(area);
+            if part.width() < 1.0 || part.height() < 1.0 {
+                return None;
+            }
+            Some((*m, part.translate(-m.rect().min.to_vec2())))
+        })
+        .collect()
+}
+
+//Builds the image of `area` from captured pieces, each given with its position in the
+//virtual desktop and its pixels per point. The result uses the highest scale,
+//pieces coming from monitors with a lower one are upscaled
+pub fn compose_area(area: Rect, pieces: &[(Rect, f32, RgbaImage)]) -> Option<RgbaImage> {
+    if pieces.is_empty() {
+        return None;
+    }
+    let scale = pieces.iter().map(|p| p.1).fold(1.0_f32, f32::max);
+
+    let width = (area.width() * scale).round() as u32;
+    let height = (area.height() * scale).round() as u32;
+    let mapping = ImageMapping::new(area, width, height);
+    let mut composed = RgbaImage::new(width, height);
+    for (rect, _, capture) in pieces {
+        let (left, top, target_w, target_h) = match mapping.pixel_rect(*rect) {
+            Some(target) => target,
+            None => continue,
+        };
+        if capture.width() == target_w && capture.height() == target_h {
+            imageops::replace(&mut composed, capture, left as i64, top as i64);
+        } else {
+            let resized = imageops::resize(capture, target_w, target_h, imageops::FilterType::Triangle);
+            imageops::replace(&mut composed, &resized, left as i64, top as i64);
+        }
+    }
+    Some(composed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn monitor(id: u32, x: i32, y: i32, width: u32, height: u32, scale_factor: f32) -> Monitor {
+        Monitor {
+            id,
+            x,
+            y,
+            width,
+            height,
+            scale_factor,
+            is_primary: id == 0,
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect::from_min_size(Pos2::new(x, y), Vec2::new(width, height))
+    }
+
+    #[test]
+    fn bounds_with_a_monitor_on_the_left() {
+        let monitors = [monitor(0, 0, 0, 1920, 1080, 1.0), monitor(1, -1280, 100, 1280, 1024, 1.0)];
+
+        assert_eq!(virtual_bounds(&monitors), Some((-1280, 0, 3200, 1124)));
+    }
+
+    #[test]
+    fn bounds_with_a_monitor_above() {
+        let monitors = [monitor(0, 0, 0, 1920, 1080, 1.0), monitor(1, 160, -900, 1600, 900, 1.0)];
+
+        assert_eq!(virtual_bounds(&monitors), Some((0, -900, 1920, 1980)));
+    }
+
+    #[test]
+    fn bounds_with_negative_origins_only() {
+        let monitors = [monitor(0, -1920, -1080, 1920, 1080, 1.0), monitor(1, -3200, -1080, 1280, 720, 1.0)];
+
+        assert_eq!(virtual_bounds(&monitors), Some((-3200, -1080, 3200, 1080)));
+    }
+
+    #[test]
+    fn layout_keeps_monitors_side_by_side() {
+        let monitors = [monitor(0, 0, 0, 1000, 500, 1.0), monitor(1, -1000, 0, 1000, 500, 1.0)];
+
+        let layout = fit_layout(&monitors, rect(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(layout[1].1, rect(0.0, 25.0, 100.0, 50.0));
+        assert_eq!(layout[0].1, rect(100.0, 25.0, 100.0, 50.0));
+    }
+
+    #[test]
+    fn layout_keeps_monitors_stacked() {
+        let monitors = [monitor(0, 0, 0, 1000, 500, 1.0), monitor(1, 0, -500, 1000, 500, 1.0)];
+
+        let layout = fit_layout(&monitors, rect(0.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(layout[1].1, rect(0.0, 0.0, 100.0, 50.0));
+        assert_eq!(layout[0].1, rect(0.0, 50.0, 100.0, 50.0));
+    }
+
+    #[test]
+    fn split_gives_parts_relative_to_each_monitor() {
+        let monitors = [
+            monitor(0, 0, 0, 1920, 1080, 1.0),
+            monitor(1, -1280, 0, 1280, 1024, 1.0),
+            monitor(2, 0, -900, 1600, 900, 1.0),
+        ];
+
+        let parts = split_by_monitor(&monitors, rect(-100.0, -50.0, 300.0, 200.0));
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], (monitors[0], rect(0.0, 0.0, 200.0, 150.0)));
+        assert_eq!(parts[1], (monitors[1], rect(1180.0, 0.0, 100.0, 150.0)));
+        assert_eq!(parts[2], (monitors[2], rect(0.0, 850.0, 200.0, 50.0)));
+    }
+
+    #[test]
+    fn split_skips_monitors_outside_the_area() {
+        let monitors = [monitor(0, 0, 0, 1920, 1080, 1.0), monitor(1, -1280, 0, 1280, 1024, 1.0)];
+
+        let parts = split_by_monitor(&monitors, rect(-1000.0, -300.0, 500.0, 400.0));
+
+        assert_eq!(parts, vec![(monitors[1], rect(280.0, 0.0, 500.0, 100.0))]);
+    }
+
+    #[test]
+    fn compose_upscales_pieces_of_lower_scale() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        //a 1x monitor on the left of a 2x one, both 100x50 points
+        let pieces = vec![
+            (rect(-100.0, 0.0, 100.0, 50.0), 1.0, RgbaImage::from_pixel(100, 50, red)),
+            (rect(0.0, 0.0, 100.0, 50.0), 2.0, RgbaImage::from_pixel(200, 100, blue)),
+        ];
+
+        let composed = compose_area(rect(-100.0, 0.0, 200.0, 50.0), &pieces).unwrap();
+
+        assert_eq!(composed.dimensions(), (400, 100));
+        assert_eq!(*composed.get_pixel(0, 0), red);
+        assert_eq!(*composed.get_pixel(199, 99), red);
+        assert_eq!(*composed.get_pixel(200, 0), blue);
+        assert_eq!(*composed.get_pixel(399, 99), blue);
+    }
+
+    #[test]
+    fn desktop_puts_every_capture_in_place() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        //a 1.5x monitor above a 1x one, with a gap on its right
+        let captures = vec![
+            (monitor(0, 0, 0, 200, 100, 1.0), RgbaImage::from_pixel(200, 100, red)),
+            (monitor(1, 0, -40, 100, 40, 1.5), RgbaImage::from_pixel(150, 60, blue)),
+        ];
+
+        let desktop = compose_virtual_desktop(&captures).unwrap();
+
+        assert_eq!(desktop.dimensions(), (300, 210));
+        assert_eq!(*desktop.get_pixel(0, 0), blue);
+        assert_eq!(*desktop.get_pixel(149, 59), blue);
+        assert_eq!(desktop.get_pixel(150, 0).0[3], 0);
+        assert_eq!(*desktop.get_pixel(0, 60), red);
+        assert_eq!(*desktop.get_pixel(299, 209), red);
+    }
+}
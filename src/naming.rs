@@ -0,0 +1,227 @@
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+
+pub const DEFAULT_TEMPLATE: &str = "{date:%Y-%m-%d}_{time}";
+
+pub const TOKENS_HELP: &str =
+    "{date}, {date:%Y%m%d}, {time}, {time:%H%M}, {counter}, {counter:3}, {monitor}, {mode}, {width}, {height}";
+
+pub struct NamingContext {
+    pub date: DateTime<Local>,
+    pub counter: u32,
+    pub monitor: usize,
+    pub mode: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl NamingContext {
+    pub fn new(counter: u32, monitor: usize, mode: &str, width: u32, height: u32) -> Self {
+        NamingContext {
+            date: Local::now(),
+            counter,
+            monitor,
+            mode: mode.to_string(),
+            width,
+            height,
+        }
+    }
+}
+
+//Expands every {token} of the template, unknown tokens are kept as they are
+pub fn render_template(template: &str, ctx: &NamingContext) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let token = &after[..end];
+                match expand_token(token, ctx) {
+                    Some(value) => out.push_str(&sanitize(&value)),
+                    None => {
+                        out.push('{');
+                        out.push_str(token);
+                        out.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn expand_token(token: &str, ctx: &NamingContext) -> Option<String> {
+    let (name, arg) = match token.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (token, None),
+    };
+
+    match name {
+        "date" => format_date(ctx, arg.unwrap_or("%Y-%m-%d")),
+        "time" => format_date(ctx, arg.unwrap_or("%H_%M_%S")),
+        "counter" => {
+            let digits = arg.and_then(|a| a.parse::<usize>().ok()).unwrap_or(0);
+            Some(format!("{:0width$}", ctx.counter, width = digits))
+        }
+        "monitor" => Some(ctx.monitor.to_string()),
+        "mode" => Some(ctx.mode.clone()),
+        "width" => Some(ctx.width.to_string()),
+        "height" => Some(ctx.height.to_string()),
+        _ => None,
+    }
+}
+
+fn format_date(ctx: &NamingContext, fmt: &str) -> Option<String> {
+    //an invalid specifier would make chrono panic while formatting
+    let items: Vec<Item> = StrftimeItems::new(fmt).collect();
+    if items.contains(&Item::Error) {
+        return None;
+    }
+    Some(ctx.date.format_with_items(items.into_iter()).to_string())
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            _ => c,
+        })
+        .collect()
+}
+
+//Builds the destination file for a new capture: subdirectories in the template
+//are created and an existing file is never overwritten, a _1, _2, ... suffix is added instead
+pub fn build_file_path(
+    dir: &Path,
+    template: &str,
+    ctx: &NamingContext,
+    extension: &str,
+) -> std::io::Result<PathBuf> {
+    let mut rendered = render_template(template, ctx).replace('\\', "/");
+    if rendered.ends_with('/') {
+        rendered.push_str(&render_template(DEFAULT_TEMPLATE, ctx));
+    }
+    //characters typed in the template are checked as well as the expanded values
+    let mut parts: Vec<String> = rendered
+        .split('/')
+        .filter(|p| !p.trim().is_empty() && *p != "." && *p != "..")
+        .map(sanitize)
+        .collect();
+    if parts.is_empty() {
+        parts.push(render_template(DEFAULT_TEMPLATE, ctx));
+    }
+
+    let mut path = dir.to_path_buf();
+    for part in parts {
+        path.push(part);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let stem = path.file_name().unwrap().to_string_lossy().to_string();
+    let mut candidate = path.with_file_name(format!("{}.{}", stem, extension));
+    let mut suffix = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{}_{}.{}", stem, suffix, extension));
+        suffix += 1;
+    }
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> NamingContext {
+        NamingContext {
+            date: Local.with_ymd_and_hms(2024, 3, 5, 14, 7, 9).unwrap(),
+            counter: 7,
+            monitor: 1,
+            mode: "rectangle".to_string(),
+            width: 640,
+            height: 480,
+        }
+    }
+
+    //a fresh directory for each test, so that they can run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("naming_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn placeholders_are_expanded() {
+        let ctx = context();
+
+        assert_eq!(render_template("{date}_{time}", &ctx), "2024-03-05_14_07_09");
+        assert_eq!(render_template("{date:%Y%m%d}-{time:%H%M}", &ctx), "20240305-1407");
+        assert_eq!(render_template("shot{counter}_{counter:3}", &ctx), "shot7_007");
+        assert_eq!(render_template("{mode}_m{monitor}_{width}x{height}", &ctx), "rectangle_m1_640x480");
+        assert_eq!(render_template(DEFAULT_TEMPLATE, &ctx), "2024-03-05_14_07_09");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        let ctx = context();
+
+        assert_eq!(render_template("{nothing}_{counter}", &ctx), "{nothing}_7");
+        assert_eq!(render_template("open {counter", &ctx), "open {counter");
+        //chrono would panic on an invalid specifier
+        assert_eq!(render_template("{date:%Q}", &ctx), "{date:%Q}");
+    }
+
+    #[test]
+    fn illegal_characters_are_replaced() {
+        let ctx = context();
+
+        assert_eq!(render_template("{time:%H:%M}", &ctx), "14-07");
+        assert_eq!(sanitize("a:b*c?d\"e<f>g|h"), "a-b-c-d-e-f-g-h");
+    }
+
+    #[test]
+    fn paths_stay_inside_the_directory() {
+        let dir = temp_dir("inside");
+        let ctx = context();
+
+        let up = build_file_path(&dir, "../../{counter}", &ctx, "png").unwrap();
+        let absolute = build_file_path(&dir, "/etc/{counter}", &ctx, "png").unwrap();
+        let windows = build_file_path(&dir, "..\\sub\\.\\{counter}", &ctx, "png").unwrap();
+        let typed = build_file_path(&dir, "C:/a|b/{counter}?", &ctx, "png").unwrap();
+
+        assert_eq!(up, dir.join("7.png"));
+        assert_eq!(absolute, dir.join("etc").join("7.png"));
+        assert_eq!(windows, dir.join("sub").join("7.png"));
+        assert_eq!(typed, dir.join("C-").join("a-b").join("7-.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_names_fall_back_to_the_default() {
+        let dir = temp_dir("default");
+        let ctx = context();
+
+        let empty = build_file_path(&dir, "  ", &ctx, "png").unwrap();
+        let folder = build_file_path(&dir, "{mode}/", &ctx, "png").unwrap();
+
+        assert_eq!(empty, dir.join("2024-03-05_14_07_09.png"));
+        assert_eq!(folder, dir.join("rectangle").join("2024-03-05_14_07_09.png"));
+        assert!(dir.join("rectangle").is_dir());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
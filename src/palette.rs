@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use egui::Color32;
+
+const MAX_COLORS: usize = 12;
+
+//rrggbbaa, the alpha is not premultiplied
+pub fn to_hex(c: Color32) -> String {
+    c.to_srgba_unmultiplied().iter().map(|v| format!("{:02x}", v)).collect()
+}
+
+pub fn from_hex(s: &str) -> Option<Color32> {
+    if s.len() != 8 {
+        return None;
+    }
+    let v = |i: usize| u8::from_str_radix(s.get(i..i + 2)?, 16).ok();
+    Some(Color32::from_rgba_unmultiplied(v(0)?, v(2)?, v(4)?, v(6)?))
+}
+
+//Colors used last by any tool of the editor, kept in a file one per line
+#[derive(Debug, Clone, Default)]
+pub struct RecentColors {
+    path: Option<PathBuf>,
+    colors: Vec<Color32>,
+}
+
+impl RecentColors {
+    //a missing or unreadable file simply gives no colors
+    pub fn load(path: &Path) -> Self {
+        let colors = std::fs::read_to_string(path)
+            .map(|content| content.lines().filter_map(from_hex).collect())
+            .unwrap_or_default();
+        RecentColors {
+            path: Some(path.to_path_buf()),
+            colors,
+        }
+    }
+
+    //most recent first
+    pub fn get_colors(&self) -> &[Color32] {
+        &self.colors
+    }
+
+    pub fn add(&mut self, color: Color32) -> std::io::Result<()> {
+        if self.colors.first() == Some(&color) {
+            return Ok(());
+        }
+        self.colors.retain(|c| *c != color);
+        self.colors.insert(0, color);
+        self.colors.truncate(MAX_COLORS);
+        self.save()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = std::fs::File::create(path)?;
+        for c in self.colors.iter() {
+            writeln!(file, "{}", to_hex(*c))?;
+        }
+        Ok(())
+    }
+}
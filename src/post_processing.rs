@@ -4,22 +4,32 @@ use egui::{
     vec2, Color32, CursorIcon, Painter, Pos2, Rect, Response, RichText, Rounding, Sense, Shape,
     Stroke, Ui, Vec2,
 };
+
+use crate::coords::{self, ImageMapping};
+use crate::palette::RecentColors;
+use crate::style::Style;
+use crate::transform::Geometry;
+
+//zoom of the editor canvas, 1 fits the image in the window
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 32.0;
+//room left around the canvas for its frame in the scroll area
+const CANVAS_MARGIN: f32 = 12.0;
 pub trait View {
     fn ui(
         &mut self,
         ui: &mut egui::Ui,
         image: egui::Image,
-        mult_fact: &mut Option<(f32, f32)>,
-        dim: Vec2,
+        full_size: Vec2,
         opt: PpOptions,
         save: bool,
         cut_clicked: bool,
     ) -> (
-        Option<Vec<(Vec<Pos2>, Color32)>>,
-        Option<Vec<(Vec<Pos2>, Color32)>>,
-        Option<Vec<(Pos2, Color32, String)>>,
-        Option<Vec<(Rect, Color32)>>,
-        Option<Vec<(Pos2, f32, Color32)>>,
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Pos2, Style, String)>>,
+        Option<Vec<(Rect, Style)>>,
+        Option<Vec<(Pos2, f32, Style)>>,
         Option<Response>,
     );
 }
@@ -38,96 +48,155 @@ pub enum PpOptions {
     Text,
     Painting,
     Cut,
+    //picks a shape to change its style
+    Select,
+    //only used in the undo history, for rotations, flips and resizes
+    Transform,
+}
+
+//What main has to do to the image after a transform was undone or redone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryStep {
+    Undo,
+    Redo,
 }
 pub struct Painting {
     last_type_added: Vec<PpOptions>,
     last_type_removed: Vec<PpOptions>,
 
-    mult_factor: Option<(f32, f32)>,
+    //the canvas drawn last and the pixels of the image shown on it
+    canvas_mapping: Option<ImageMapping>,
 
-    lines: Vec<(Vec<Pos2>, Color32)>,
-    lines_color: egui::Color32,
+    //style of the next shapes, every tool shares it
+    style: Style,
+    //the shape picked with the Select tool, its kind and index
+    selected: Option<(PpOptions, usize)>,
+    //the style of a shape already drawn was changed
+    restyled: bool,
+
+    lines: Vec<(Vec<Pos2>, Style)>,
 
     starting_point: Pos2,
     final_point: Pos2,
-    arrows: Vec<(Pos2, Pos2, Color32)>,
-    removed_arrows: Vec<(Pos2, Pos2, Color32)>,
-    arrows_color: Color32,
-    arrows_pixels: Vec<(Vec<Pos2>, Color32)>,
+    arrows: Vec<(Pos2, Pos2, Style)>,
+    removed_arrows: Vec<(Pos2, Pos2, Style)>,
+    arrows_pixels: Vec<(Vec<Pos2>, Style)>,
 
     circle_center: Pos2,
     radius: f32,
-    circles: Vec<(Pos2, f32, Color32)>,
-    removed_circles: Vec<(Pos2, f32, Color32)>,
-    circles_color: Color32,
+    circles: Vec<(Pos2, f32, Style)>,
+    removed_circles: Vec<(Pos2, f32, Style)>,
 
     square_starting_point: Pos2,
     square_ending_point: Pos2,
-    squares_color: Color32,
-    squares: Vec<(Rect, Color32)>,
-    removed_squares: Vec<(Rect, Color32)>,
-    shift_squares: Option<Pos2>,
+    squares: Vec<(Rect, Style)>,
+    removed_squares: Vec<(Rect, Style)>,
 
     text_starting_position: Pos2,
     text_ending_position: Pos2,
-    texts_color: Color32,
-    texts: Vec<(String, Pos2, Pos2, Color32)>,
-    removed_texts: Vec<(String, Pos2, Pos2, Color32)>,
+    texts: Vec<(String, Pos2, Pos2, Style)>,
+    removed_texts: Vec<(String, Pos2, Pos2, Style)>,
     to_write_text: String,
     ready_to_write: bool,
-    counter: i32,
-    inizializzato: bool,
-    entrato: bool,
+    //the hint in the text field goes away the first time it is clicked
+    text_hint_cleared: bool,
+
+    transforms: Vec<Geometry>,
+    removed_transforms: Vec<Geometry>,
+    //waiting for the canvas of the transformed image to move the shapes onto it
+    pending_transforms: Vec<Geometry>,
+    //waiting for main to change the image back
+    deferred_transforms: Vec<Geometry>,
+    history_step: Option<HistoryStep>,
+    last_canvas: Option<Rect>,
+
+    zoom: f32,
+    //space is held: dragging pans the canvas instead of drawing
+    panning: bool,
+    scroll_offset: Vec2,
+    scroll_to: Option<Vec2>,
+    last_viewport: Option<Rect>,
+
+    recent_colors: RecentColors,
+    eyedropper: bool,
+    //where the eyedropper clicked, in fractions of the image, for main to sample
+    picked: Option<Pos2>,
 }
 
-
 impl Default for Painting {
     fn default() -> Self {
         Self {
             last_type_added: Vec::new(),
             last_type_removed: Vec::new(),
 
-            mult_factor: None,
+            canvas_mapping: None,
+            style: Style::default(),
+            selected: None,
+            restyled: false,
+
             lines: Default::default(),
-            lines_color: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
 
             starting_point: Pos2 { x: -1.0, y: -1.0 },
             final_point: Pos2 { x: -1.0, y: -1.0 },
             arrows: Vec::new(),
             removed_arrows: Vec::new(),
-            arrows_color: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
             arrows_pixels: Vec::new(),
 
             circle_center: Pos2 { x: -1.0, y: -1.0 },
             radius: -1.0,
             circles: Vec::new(),
             removed_circles: Vec::new(),
-            circles_color: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
 
             square_starting_point: Pos2 { x: -1.0, y: -1.0 },
             square_ending_point: Pos2 { x: -1.0, y: -1.0 },
-            squares_color: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
             squares: Vec::new(),
             removed_squares: Vec::new(),
-            shift_squares: None,
 
             text_starting_position: Pos2 { x: -1.0, y: -1.0 },
             text_ending_position: Pos2 { x: -1.0, y: -1.0 },
             texts: Vec::new(),
             removed_texts: Vec::new(),
-            texts_color: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
             to_write_text: "Write something".to_string(),
             ready_to_write: false,
-            counter: 0,
-            inizializzato: false,
-            entrato: false,
+            text_hint_cleared: false,
+
+            transforms: Vec::new(),
+            removed_transforms: Vec::new(),
+            pending_transforms: Vec::new(),
+            deferred_transforms: Vec::new(),
+            history_step: None,
+            last_canvas: None,
+
+            zoom: 1.0,
+            panning: false,
+            scroll_offset: Vec2::ZERO,
+            scroll_to: None,
+            last_viewport: None,
+
+            recent_colors: RecentColors::default(),
+            eyedropper: false,
+            picked: None,
         }
     }
 }
 
 impl Painting {
-    
+    //Links the canvas just allocated to the pixels of the image shown on it, every
+    //shape is converted to the image through it
+    fn map_canvas(&mut self, canvas: Rect, image: &egui::Image) -> ImageMapping {
+        let size = image.size().unwrap_or(canvas.size());
+        let mapping = ImageMapping::new(canvas, size.x.round() as u32, size.y.round() as u32);
+        self.canvas_mapping = Some(mapping);
+        mapping
+    }
+
+    //image pixels shown by a point of the canvas
+    fn pixels_per_point(&self) -> Vec2 {
+        self.canvas_mapping.map_or(Vec2::splat(1.0), |m| m.scale())
+    }
+
     pub fn render_elements(&mut self, painter: Painter, to_screen: emath::RectTransform) {
+        let Vec2 { x: px, y: py } = self.pixels_per_point();
         if !self.lines.is_empty() {
             let shapes = self
                 .lines
@@ -135,7 +204,7 @@ impl Painting {
                 .filter(|line| line.0.len() >= 2)
                 .map(|line| {
                     let points: Vec<Pos2> = line.0.iter().map(|p| to_screen * *p).collect();
-                    egui::Shape::line(points, Stroke::new(0.5, line.1))
+                    egui::Shape::line(points, line.1.screen_stroke(px))
                 });
             painter.extend(shapes);
         }
@@ -144,12 +213,13 @@ impl Painting {
                 painter.arrow(
                     point.0,
                     vec2(point.1.x - point.0.x, point.1.y - point.0.y),
-                    Stroke::new(0.5, point.2),
+                    point.2.screen_stroke(px),
                 );
-                let pixels = self
-                    .calc_pixels_arrow(point.0, vec2(point.1.x - point.0.x, point.1.y - point.0.y));
-                if !self.arrows_pixels.contains(&(pixels.clone(), point.2)) {
-                    self.arrows_pixels.push((pixels, point.2));
+                if let Some(mapping) = self.canvas_mapping {
+                    let pixels = arrow_in_pixels(mapping, point.0, point.1 - point.0);
+                    if !self.arrows_pixels.contains(&(pixels.clone(), point.2)) {
+                        self.arrows_pixels.push((pixels, point.2));
+                    }
                 }
             }
         }
@@ -159,19 +229,19 @@ impl Painting {
                 painter.circle(
                     point.0,
                     point.1,
-                    egui::Color32::TRANSPARENT,
-                    Stroke::new(0.5, point.2),
+                    point.2.fill_color(),
+                    point.2.screen_stroke(px),
                 );
             }
         }
 
-        if !self.squares.is_empty() || self.squares.len() == 0 {
+        if !self.squares.is_empty() {
             for point in self.squares.clone().into_iter() {
                 painter.rect(
                     point.0,
                     0.0,
-                    egui::Color32::TRANSPARENT,
-                    Stroke::new(0.5, point.1),
+                    point.1.fill_color(),
+                    point.1.screen_stroke(px),
                 );
             }
         }
@@ -182,14 +252,13 @@ impl Painting {
                     point.1,
                     egui::Align2::LEFT_TOP,
                     point.0,
-                    egui::FontId::monospace(15.0),
-                    point.3,
+                    egui::FontId::monospace(point.3.font_size / py),
+                    point.3.stroke_color(),
                 );
             }
         }
     }
     fn undo(&mut self) {
-     
         match self.last_type_added.last().unwrap() {
             PpOptions::Arrow => {
                 let rem = self.arrows.pop().unwrap();
@@ -200,7 +269,7 @@ impl Painting {
             PpOptions::Circle => {
                 let rem = self.circles.pop().unwrap();
                 self.removed_circles.push(rem);
-            
+
                 self.last_type_removed.push(PpOptions::Circle);
             }
             PpOptions::Square => {
@@ -213,12 +282,19 @@ impl Painting {
                 self.removed_texts.push(rem);
                 self.last_type_removed.push(PpOptions::Text);
             }
+            PpOptions::Transform => {
+                let rem = self.transforms.pop().unwrap();
+                self.deferred_transforms.push(rem.inverse());
+                self.history_step = Some(HistoryStep::Undo);
+                self.removed_transforms.push(rem);
+                self.last_type_removed.push(PpOptions::Transform);
+            }
             _ => {}
         }
         self.last_type_added.pop();
+        self.selected = None;
     }
     fn redo(&mut self) {
-
         match self.last_type_removed.last().unwrap() {
             PpOptions::Arrow => {
                 let rem = self.removed_arrows.pop().unwrap();
@@ -241,171 +317,426 @@ impl Painting {
                 self.texts.push(rem);
                 self.last_type_added.push(PpOptions::Text);
             }
+            PpOptions::Transform => {
+                let rem = self.removed_transforms.pop().unwrap();
+                self.deferred_transforms.push(rem);
+                self.history_step = Some(HistoryStep::Redo);
+                self.transforms.push(rem);
+                self.last_type_added.push(PpOptions::Transform);
+            }
             _ => {}
         }
         self.last_type_removed.pop();
+        self.selected = None;
     }
 
-    pub fn ui_control(&mut self, ui: &mut egui::Ui, opt: PpOptions) -> egui::Response {
-        let res ;
+    //The image was just transformed: the shapes follow it on the next canvas
+    pub fn record_transform(&mut self, geometry: Geometry) {
+        self.transforms.push(geometry);
+        self.pending_transforms.push(geometry);
+        self.last_type_added.push(PpOptions::Transform);
+        self.removed_transforms.clear();
+        self.last_type_removed
+            .retain(|t| !matches!(t, PpOptions::Transform));
+    }
+
+    //Taken by main right after the undo or redo of a transform, once it has changed
+    //the image back the shapes can follow it
+    pub fn take_history_step(&mut self) -> Option<HistoryStep> {
+        let step = self.history_step.take();
+        if step.is_some() {
+            self.pending_transforms
+                .append(&mut self.deferred_transforms);
+        }
+        step
+    }
+
+    //Called with the rect the image is drawn in, before anything is drawn on it.
+    //Shapes are kept in screen coordinates, so when the canvas is zoomed, scrolled
+    //or the image transformed they are moved from the previous canvas to the new
+    //one through the fractions of the image
+    fn follow_canvas(&mut self, canvas: Rect) {
+        let pending = std::mem::take(&mut self.pending_transforms);
+        let old = match self.last_canvas.replace(canvas) {
+            Some(old)
+                if (old != canvas || !pending.is_empty())
+                    && old.width() > 0.0
+                    && old.height() > 0.0 =>
+            {
+                old
+            }
+            _ => return,
+        };
+        let map = |p: Pos2| {
+            let mut f = Pos2::new(
+                (p.x - old.min.x) / old.width(),
+                (p.y - old.min.y) / old.height(),
+            );
+            for geometry in pending.iter() {
+                f = geometry.map(f);
+            }
+            Pos2::new(
+                canvas.min.x + f.x * canvas.width(),
+                canvas.min.y + f.y * canvas.height(),
+            )
+        };
+        let map_circle = |c: (Pos2, f32, Style)| {
+            let center = map(c.0);
+            (center, (map(c.0 + vec2(c.1, 0.0)) - center).length(), c.2)
+        };
+
+        let old_lines = emath::RectTransform::from_to(
+            Rect::from_min_size(Pos2::ZERO, old.square_proportions()),
+            old,
+        );
+        let new_lines = emath::RectTransform::from_to(
+            Rect::from_min_size(Pos2::ZERO, canvas.square_proportions()),
+            canvas,
+        )
+        .inverse();
+        for line in self.lines.iter_mut() {
+            for p in line.0.iter_mut() {
+                *p = new_lines * map(old_lines * *p);
+            }
+        }
+        for a in self.arrows.iter_mut().chain(self.removed_arrows.iter_mut()) {
+            *a = (map(a.0), map(a.1), a.2);
+        }
+        //rebuilt from the arrows while they are drawn
+        self.arrows_pixels.clear();
+        for c in self
+            .circles
+            .iter_mut()
+            .chain(self.removed_circles.iter_mut())
+        {
+            *c = map_circle(*c);
+        }
+        for s in self
+            .squares
+            .iter_mut()
+            .chain(self.removed_squares.iter_mut())
+        {
+            *s = (Rect::from_two_pos(map(s.0.min), map(s.0.max)), s.1);
+        }
+        for t in self.texts.iter_mut().chain(self.removed_texts.iter_mut()) {
+            t.1 = map(t.1);
+            t.2 = map(t.2);
+        }
+        //the place clicked for a text still to be written
+        if self.text_starting_position.x != -1.0 {
+            self.text_starting_position = map(self.text_starting_position);
+            self.text_ending_position = map(self.text_ending_position);
+        }
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.0;
+        self.scroll_to = Some(Vec2::ZERO);
+    }
+
+    //Changes the zoom keeping the point under `anchor` (the middle of the view when
+    //None) where it is
+    fn set_zoom(&mut self, zoom: f32, anchor: Option<Pos2>) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if let Some(viewport) = self.last_viewport {
+            let local = anchor.unwrap_or(viewport.center()) - viewport.min;
+            self.scroll_to = Some((self.scroll_offset + local) * (zoom / self.zoom) - local);
+        }
+        self.zoom = zoom;
+    }
+
+    fn canvas_sense(&self) -> Sense {
+        if self.panning {
+            Sense::hover()
+        } else {
+            Sense::drag()
+        }
+    }
+
+    //The canvas showing an image `full_size` large (points) in the space left in `ui`
+    //below `reserved_height`
+    fn fit_canvas(ui: &Ui, full_size: Vec2, reserved_height: f32) -> Vec2 {
+        let room = ui.available_size() - Vec2::new(CANVAS_MARGIN, CANVAS_MARGIN + reserved_height);
+        coords::fit(full_size, room.max(Vec2::splat(1.0)))
+    }
+
+    //Shows the canvas fitted in the window, zoomed with Ctrl+wheel and panned
+    //with the scrollbars or by dragging while holding space
+    fn zoomable_canvas(
+        &mut self,
+        ui: &mut Ui,
+        image_size: Vec2,
+        full_size: Vec2,
+        add_contents: impl FnOnce(&mut Self, &mut Ui, Vec2),
+    ) {
+        //the row of the zoom buttons comes first
+        let dim = Self::fit_canvas(
+            ui,
+            full_size,
+            ui.spacing().interact_size.y + ui.spacing().item_spacing.y,
+        );
+        //zoom at which a pixel of the image takes a pixel of the screen
+        let actual_size = image_size.x / ui.ctx().pixels_per_point() / dim.x.max(1.0);
+        ui.horizontal(|ui| {
+            if ui.button("Fit").clicked() {
+                self.reset_zoom();
+            }
+            if ui.button("100%").clicked() {
+                self.set_zoom(actual_size, None);
+            }
+            if ui.button("-").clicked() {
+                self.set_zoom(self.zoom / 1.25, None);
+            }
+            if ui.button("+").clicked() {
+                self.set_zoom(self.zoom * 1.25, None);
+            }
+            ui.label(format!("{:.0}%", self.zoom / actual_size * 100.0))
+                .on_hover_text("Ctrl+wheel to zoom, hold space and drag to pan");
+        });
+
+        let (zoom_delta, pointer) = ui.input(|i| (i.zoom_delta(), i.pointer.hover_pos()));
+        if zoom_delta != 1.0 {
+            if let (Some(viewport), Some(pointer)) = (self.last_viewport, pointer) {
+                if viewport.contains(pointer) {
+                    self.set_zoom(self.zoom * zoom_delta, Some(pointer));
+                }
+            }
+        }
+        //space typed in a text field must not pan
+        self.panning =
+            ui.input(|i| i.key_down(egui::Key::Space)) && ui.memory(|m| m.focus().is_none());
+        if self.panning {
+            ui.ctx().output_mut(|o| o.cursor_icon = CursorIcon::Grab);
+        }
+
+        let mut area = egui::ScrollArea::both()
+            .max_width(dim.x + CANVAS_MARGIN)
+            .max_height(dim.y + CANVAS_MARGIN)
+            .drag_to_scroll(self.panning);
+        if let Some(offset) = self.scroll_to.take() {
+            area = area.scroll_offset(offset.max(Vec2::ZERO));
+        }
+        let zoom = self.zoom;
+        let output = area.show(ui, |ui| {
+            egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                add_contents(self, ui, dim * zoom);
+            });
+        });
+        self.scroll_offset = output.state.offset;
+        self.last_viewport = Some(output.inner_rect);
+
+        if self.eyedropper {
+            if let Some(canvas) = self.last_canvas {
+                let visible = canvas.intersect(output.inner_rect);
+                let click = ui.input(|i| {
+                    i.pointer
+                        .interact_pos()
+                        .filter(|_| i.pointer.primary_clicked())
+                });
+                if let Some(p) = click.filter(|p| visible.contains(*p)) {
+                    self.picked = Some(Pos2::new(
+                        (p.x - canvas.min.x) / canvas.width(),
+                        (p.y - canvas.min.y) / canvas.height(),
+                    ));
+                    self.eyedropper = false;
+                }
+            }
+        }
+    }
+
+    pub fn set_recent_colors(&mut self, recent_colors: RecentColors) {
+        self.recent_colors = recent_colors;
+    }
+
+    pub fn take_color_pick(&mut self) -> Option<Pos2> {
+        self.picked.take()
+    }
+
+    fn color_of(&self, opt: &PpOptions) -> Option<Color32> {
         match opt {
-            PpOptions::Painting => {
-                if self.lines.last_mut() == None {
-                    res = Some(
-                        ui.horizontal(|ui| {
-                            ui.color_edit_button_srgba(&mut self.lines_color);
-
-                            ui.separator();
-                            if ui.button("Clear Painting").clicked() {
-                                self.lines.clear();
-                            }
-                        })
-                        .response,
-                    );
-                    res.unwrap()
-                } else {
-                    let res = ui
-                        .horizontal(|ui| {
-                            ui.color_edit_button_srgba(&mut self.lines.last_mut().unwrap().1);
-
-                            ui.separator();
-                            if ui.button("Clear Painting").clicked() {
-                                self.lines.clear();
-                            }
-                        })
-                        .response;
-                    if !self.lines.is_empty() {
-                        self.lines_color = self.lines.last_mut().unwrap().1;
-                    }
+            PpOptions::Cut | PpOptions::Transform | PpOptions::Select => None,
+            _ => Some(self.style.stroke),
+        }
+    }
+
+    //The style the toolbar shows and edits: the one of the selected shape if any,
+    //otherwise the one the next shapes get
+    fn target_style(&self) -> Style {
+        match &self.selected {
+            Some((PpOptions::Painting, i)) => self.lines.get(*i).map(|l| l.1),
+            Some((PpOptions::Arrow, i)) => self.arrows.get(*i).map(|a| a.2),
+            Some((PpOptions::Circle, i)) => self.circles.get(*i).map(|c| c.2),
+            Some((PpOptions::Square, i)) => self.squares.get(*i).map(|s| s.1),
+            Some((PpOptions::Text, i)) => self.texts.get(*i).map(|t| t.3),
+            _ => None,
+        }
+        .unwrap_or(self.style)
+    }
 
-                    res
+    fn set_target_style(&mut self, style: Style) {
+        let shape_style = match &self.selected {
+            Some((PpOptions::Painting, i)) => self.lines.get_mut(*i).map(|l| &mut l.1),
+            Some((PpOptions::Arrow, i)) => self.arrows.get_mut(*i).map(|a| &mut a.2),
+            Some((PpOptions::Circle, i)) => self.circles.get_mut(*i).map(|c| &mut c.2),
+            Some((PpOptions::Square, i)) => self.squares.get_mut(*i).map(|s| &mut s.1),
+            Some((PpOptions::Text, i)) => self.texts.get_mut(*i).map(|t| &mut t.3),
+            _ => None,
+        };
+        match shape_style {
+            Some(shape_style) => {
+                *shape_style = style;
+                self.restyled = true;
+                //rebuilt from the arrows while they are drawn
+                self.arrows_pixels.clear();
+            }
+            None => {
+                self.style = style;
+                //the line that the next stroke will fill
+                if let Some(line) = self.lines.last_mut().filter(|l| l.0.is_empty()) {
+                    line.1 = style;
                 }
             }
-            PpOptions::Text => {
+        }
+    }
+
+    //Sets the stroke color of the selected shape or of the next ones
+    pub fn set_color(&mut self, color: Color32) {
+        let mut style = self.target_style();
+        style.stroke = color;
+        self.set_target_style(style);
+        self.remember_color(color);
+    }
+
+    //a line counts as soon as it is started
+    fn shapes_count(&self) -> usize {
+        self.last_type_added.len() + self.lines.iter().filter(|l| !l.0.is_empty()).count()
+    }
+
+    fn remember_color(&mut self, color: Color32) {
+        //the palette is a convenience, a file that can't be written only loses it
+        let _ = self.recent_colors.add(color);
+    }
+
+    //Eyedropper and the colors used last, shown next to the color of every tool
+    fn palette_buttons(&mut self, ui: &mut Ui) {
+        if ui
+            .selectable_label(self.eyedropper, "💧")
+            .on_hover_text("Pick a color from the image")
+            .clicked()
+        {
+            self.eyedropper = !self.eyedropper;
+        }
+        for color in self.recent_colors.get_colors().to_vec() {
+            let swatch = egui::Button::new("").fill(color).min_size(vec2(16.0, 16.0));
+            if ui.add(swatch).clicked() {
+                self.set_color(color);
+            }
+        }
+    }
 
-                ui.horizontal(|ui: &mut Ui| {
-                    ui.color_edit_button_srgba(&mut self.texts_color);
-                 
+    fn history_buttons(&mut self, ui: &mut Ui) {
+        if ui
+            .add_enabled(!self.last_type_added.is_empty(), egui::Button::new("↩"))
+            .clicked()
+        {
+            self.undo();
+        }
+        if ui
+            .add_enabled(!self.last_type_removed.is_empty(), egui::Button::new("↪"))
+            .clicked()
+        {
+            self.redo();
+        }
+    }
+
+    //One toolbar for every tool: it edits the style of the next shapes, or of the
+    //selected one with the Select tool
+    fn style_toolbar(&mut self, ui: &mut Ui, opt: &PpOptions) {
+        let before = self.target_style();
+        let mut style = before;
+        ui.label("Stroke");
+        ui.color_edit_button_srgba(&mut style.stroke);
+        if matches!(
+            opt,
+            PpOptions::Circle | PpOptions::Square | PpOptions::Select
+        ) {
+            ui.label("Fill");
+            ui.color_edit_button_srgba(&mut style.fill);
+        }
+        if !matches!(opt, PpOptions::Text) {
+            ui.add(
+                egui::DragValue::new(&mut style.width)
+                    .clamp_range(1.0..=50.0)
+                    .speed(0.2)
+                    .prefix("width ")
+                    .suffix(" px"),
+            );
+        }
+        if matches!(opt, PpOptions::Text | PpOptions::Select) {
+            ui.add(
+                egui::DragValue::new(&mut style.font_size)
+                    .clamp_range(6.0..=300.0)
+                    .prefix("font ")
+                    .suffix(" px"),
+            );
+        }
+        ui.add(egui::Slider::new(&mut style.opacity, 0.0..=1.0).text("opacity"));
+        if style != before {
+            self.set_target_style(style);
+        }
+        ui.separator();
+        self.palette_buttons(ui);
+        ui.separator();
+        self.history_buttons(ui);
+    }
+
+    pub fn ui_control(&mut self, ui: &mut egui::Ui, opt: PpOptions) -> egui::Response {
+        if matches!(opt, PpOptions::Cut | PpOptions::Transform) {
+            return ui.horizontal(|_ui: &mut Ui| {}).response;
+        }
+        ui.horizontal(|ui| {
+            self.style_toolbar(ui, &opt);
+            match opt {
+                PpOptions::Painting => {
+                    ui.separator();
+                    if ui.button("Clear Painting").clicked() {
+                        self.lines.clear();
+                        self.selected = None;
+                    }
+                }
+                PpOptions::Text => {
                     ui.separator();
                     if ui
                         .add(egui::TextEdit::singleline(&mut self.to_write_text))
                         .clicked()
-                        == false
+                        && !self.text_hint_cleared
                     {
-                    } else {
-                        if self.entrato == false {
-                            self.entrato = true;
-                            self.to_write_text = "".to_string();
-                        }
+                        self.text_hint_cleared = true;
+                        self.to_write_text.clear();
                     }
                     ui.separator();
-                    
+
                     if ui.add(egui::Button::new("Write!")).clicked()
                         && self.text_starting_position.x != -1.0
                         && self.text_starting_position.y != -1.0
                         && self.text_ending_position.x != -1.0
                         && self.text_ending_position.y != -1.0
                     {
-                        self.entrato = true;
-                        self.to_write_text = self.to_write_text.clone();
+                        self.text_hint_cleared = true;
                         self.ready_to_write = true;
                     }
-                    if self.last_type_added.len() > 0 {
-                         
-                        if ui.add_enabled(true, egui::Button::new("↩")).clicked() {
-                            self.undo();
-                        }
-                    } else {
-                        ui.add_enabled(false, egui::Button::new("↩"));
-                    }
-                    if self.last_type_removed.len() > 0 {
-                       
-                        if ui.add_enabled(true, egui::Button::new("↪")).clicked() {
-                            self.redo();
-                        }
-                    } else {
-                        ui.add_enabled(false, egui::Button::new("↪"));
-                    }
-                    
-                })
-                .response
-            }
-            PpOptions::Cut => ui.horizontal(|_ui: &mut Ui| {}).response,
-            PpOptions::Arrow => {
-                ui.horizontal(|ui| {
-          
-                    ui.color_edit_button_srgba(&mut self.arrows_color);
-                 
-                    ui.separator();
-                    if self.last_type_added.len() > 0 {
-                         
-                        if ui.add_enabled(true, egui::Button::new("↩")).clicked() {
-                            self.undo();
-                        }
-                    } else {
-                        ui.add_enabled(false, egui::Button::new("↩"));
-                    }
-                    if self.last_type_removed.len() > 0 {
-                       
-                        if ui.add_enabled(true, egui::Button::new("↪")).clicked() {
-                            self.redo();
-                        }
-                    } else {
-                        ui.add_enabled(false, egui::Button::new("↪"));
-                    }
-                })
-                .response
-            } ,
-            PpOptions::Circle => ui.horizontal(|ui| {
-          
-                ui.color_edit_button_srgba(&mut self.circles_color);
-             
-                ui.separator();
-                if self.last_type_added.len() > 0 {
-                     
-                    if ui.add_enabled(true, egui::Button::new("↩")).clicked() {
-                        self.undo();
-                    }
-                } else {
-                    ui.add_enabled(false, egui::Button::new("↩"));
-                }
-                if self.last_type_removed.len() > 0 {
-                   
-                    if ui.add_enabled(true, egui::Button::new("↪")).clicked() {
-                        self.redo();
-                    }
-                } else {
-                    ui.add_enabled(false, egui::Button::new("↪"));
-                }
-            })
-            .response,
-            PpOptions::Square => ui.horizontal(|ui| {
-          
-                ui.color_edit_button_srgba(&mut self.squares_color);
-             
-                ui.separator();
-                if self.last_type_added.len() > 0 {
-                     
-                    if ui.add_enabled(true, egui::Button::new("↩")).clicked() {
-                        self.undo();
-                    }
-                } else {
-                    ui.add_enabled(false, egui::Button::new("↩"));
                 }
-                if self.last_type_removed.len() > 0 {
-                   
-                    if ui.add_enabled(true, egui::Button::new("↪")).clicked() {
-                        self.redo();
-                    }
-                } else {
-                    ui.add_enabled(false, egui::Button::new("↪"));
+                PpOptions::Select
+                    if ui
+                        .add_enabled(self.selected.is_some(), egui::Button::new("Deselect"))
+                        .clicked() =>
+                {
+                    self.selected = None;
                 }
-            })
-            .response,
-       
-
-          
-        }
+                _ => {}
+            }
+        })
+        .response
     }
 
     pub fn ui_content(
@@ -414,8 +745,9 @@ impl Painting {
         image: egui::Image,
         dim: Vec2,
         cut_clicked: bool,
-    ) -> (Option<Vec<(Vec<Pos2>, Color32)>>, Option<Response>) {
-        let (mut response, painter) = ui.allocate_painter(dim, Sense::drag());
+    ) -> (Option<Vec<(Vec<Pos2>, Style)>>, Option<Response>) {
+        let (mut response, painter) = ui.allocate_painter(dim, self.canvas_sense());
+        self.follow_canvas(response.rect);
 
         let to_screen = emath::RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
@@ -423,14 +755,7 @@ impl Painting {
         );
 
         image.paint_at(ui, response.rect);
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
-        self.shift_squares = Some(Pos2::new(
-            response.rect.left_top().x,
-            response.rect.left_top().y,
-        ));
+        let mapping = self.map_canvas(response.rect, &image);
         let mouse_pos = ui.input(|i| i.pointer.interact_pos());
         if mouse_pos.is_none() == false
             && response.rect.x_range().contains(mouse_pos.unwrap().x)
@@ -445,7 +770,7 @@ impl Painting {
         let from_screen = to_screen.inverse();
 
         if self.lines.is_empty() {
-            self.lines.push((vec![], self.lines_color));
+            self.lines.push((vec![], self.style));
         }
 
         let current_line = &mut self.lines.last_mut().unwrap().0;
@@ -458,36 +783,13 @@ impl Painting {
                 response.mark_changed();
             }
         } else if !current_line.is_empty() {
-            self.lines.push((vec![], self.lines_color));
+            self.lines.push((vec![], self.style));
             response.mark_changed();
         }
 
         self.render_elements(painter.clone(), to_screen);
-        let mut ret = Vec::new();
-
-        for l in self.lines.clone().into_iter() {
-            let clr = l.1;
-            let lns =
-                l.0.into_iter()
-                    .map(|f| from_screen.inverse().transform_pos(f));
-            let mut retlns = Vec::new();
-
-            for li in lns.clone().into_iter() {
-                let ps = Pos2::new(
-                    (li.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0,
-                    (li.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1,
-                );
-                retlns.push(ps);
-            }
-
-            if retlns.len()>0{
-             ret.push((retlns.clone(), clr));     
-            }      
-            
-           
-        }
 
-        (Some(ret), Some(response))
+        (Some(self.lines_in_pixels(mapping)), Some(response))
     }
 
     pub fn ui_content_arrows(
@@ -496,8 +798,9 @@ impl Painting {
         image: egui::Image,
         dim: Vec2,
         cut_clicked: bool,
-    ) -> (Option<Vec<(Vec<Pos2>, Color32)>>, Option<Response>) {
-        let (response, painter) = ui.allocate_painter(dim, Sense::drag());
+    ) -> (Option<Vec<(Vec<Pos2>, Style)>>, Option<Response>) {
+        let (response, painter) = ui.allocate_painter(dim, self.canvas_sense());
+        self.follow_canvas(response.rect);
 
         let to_screen = emath::RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
@@ -505,10 +808,7 @@ impl Painting {
         );
 
         image.paint_at(ui, response.rect);
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
+        self.map_canvas(response.rect, &image);
         let mouse_pos = ui.input(|i| i.pointer.interact_pos());
         if mouse_pos.is_none() == false
             && response.rect.x_range().contains(mouse_pos.unwrap().x)
@@ -522,7 +822,10 @@ impl Painting {
         if ui.input(|i| i.pointer.any_pressed()) && cut_clicked == false {
             let pos = ui.input(|i| i.pointer.interact_pos());
             if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
+                && response
+                    .rect
+                    .intersect(painter.clip_rect())
+                    .contains(pos.unwrap())
                 && self.starting_point.x == -1.0
                 && self.starting_point.y == -1.0
             {
@@ -543,7 +846,7 @@ impl Painting {
                         pos_dinamica.unwrap().x - self.starting_point.x,
                         pos_dinamica.unwrap().y - self.starting_point.y,
                     ),
-                    Stroke::new(0.5, self.arrows_color),
+                    self.style.screen_stroke(self.pixels_per_point().x),
                 )
             }
         }
@@ -566,15 +869,11 @@ impl Painting {
             && self.starting_point.y != -1.0
         {
             self.arrows
-                .push((self.starting_point, self.final_point, self.arrows_color));
+                .push((self.starting_point, self.final_point, self.style));
             self.starting_point = Pos2 { x: -1.0, y: -1.0 };
             self.final_point = Pos2 { x: -1.0, y: -1.0 };
             self.last_type_added.push(PpOptions::Arrow);
         }
-        self.shift_squares = Some(Pos2::new(
-            response.rect.left_top().x,
-            response.rect.left_top().y,
-        ));
 
         self.render_elements(painter.clone(), to_screen);
 
@@ -585,20 +884,17 @@ impl Painting {
         &mut self,
         ui: &mut Ui,
         image: egui::Image,
-
         dim: Vec2,
         cut_clicked: bool,
-    ) -> (Option<Vec<(Pos2, f32, Color32)>>, Option<Response>) {
-        let (response, painter) = ui.allocate_painter(dim, Sense::drag());
+    ) -> (Option<Vec<(Pos2, f32, Style)>>, Option<Response>) {
+        let (response, painter) = ui.allocate_painter(dim, self.canvas_sense());
+        self.follow_canvas(response.rect);
 
         let to_screen = emath::RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
             response.rect,
         );
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
+        let mapping = self.map_canvas(response.rect, &image);
 
         image.paint_at(ui, response.rect);
 
@@ -616,7 +912,10 @@ impl Painting {
         if ui.input(|i| i.pointer.any_pressed()) && cut_clicked == false {
             let pos = ui.input(|i| i.pointer.latest_pos());
             if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
+                && response
+                    .rect
+                    .intersect(painter.clip_rect())
+                    .contains(pos.unwrap())
                 && self.circle_center.x == -1.0
                 && self.circle_center.y == -1.0
             {
@@ -631,7 +930,7 @@ impl Painting {
                 && self.circle_center.x != -1.0
                 && self.circle_center.y != -1.0
             {
-                let distanza:f32 ;
+                let distanza: f32;
 
                 if ((pos_dinamica.unwrap().x - self.circle_center.x) / 2.0).abs()
                     >= ((pos_dinamica.unwrap().y - self.circle_center.y) / 2.0).abs()
@@ -645,7 +944,7 @@ impl Painting {
                     self.circle_center,
                     distanza,
                     egui::Color32::TRANSPARENT,
-                    Stroke::new(0.5, self.circles_color),
+                    self.style.screen_stroke(self.pixels_per_point().x),
                 );
             }
         }
@@ -669,49 +968,31 @@ impl Painting {
 
         if self.circle_center.x != -1.0 && self.circle_center.y != -1.0 && self.radius != -1.0 {
             self.circles
-                .push((self.circle_center, self.radius, self.circles_color));
+                .push((self.circle_center, self.radius, self.style));
             self.circle_center = Pos2 { x: -1.0, y: -1.0 };
             self.radius = -1.0;
             self.last_type_added.push(PpOptions::Circle);
-            self.shift_squares = Some(Pos2::new(
-                response.rect.left_top().x,
-                response.rect.left_top().y,
-            ));
         }
 
         self.render_elements(painter.clone(), to_screen);
-        let mut crcls = Vec::new();
-       
-
-        for c in self.circles.clone() {
-            let center_x = (c.0.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0;
-            let center_y = (c.0.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1;
-            let new_center = Pos2::new(center_x, center_y);
-            let new_radius = c.1 * self.mult_factor.unwrap().1;
-            crcls.push((new_center, new_radius, c.2));
-        }
-        
-        
-        (Some(crcls.clone()), Some(response))
+
+        (Some(self.circles_in_pixels(mapping)), Some(response))
     }
     pub fn ui_content_squares(
         &mut self,
         ui: &mut Ui,
         image: egui::Image,
-
         dim: Vec2,
         cut_clicked: bool,
-    ) -> (Option<Vec<(Rect, Color32)>>, Option<Response>) {
-        let (response, painter) = ui.allocate_painter(dim, Sense::drag());
+    ) -> (Option<Vec<(Rect, Style)>>, Option<Response>) {
+        let (response, painter) = ui.allocate_painter(dim, self.canvas_sense());
+        self.follow_canvas(response.rect);
 
         let to_screen = emath::RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
             response.rect,
         );
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
+        let mapping = self.map_canvas(response.rect, &image);
 
         image.paint_at(ui, response.rect);
 
@@ -729,15 +1010,13 @@ impl Painting {
         if ui.input(|i| i.pointer.any_pressed()) && cut_clicked == false {
             let pos = response.interact_pointer_pos();
             if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
+                && response
+                    .rect
+                    .intersect(painter.clip_rect())
+                    .contains(pos.unwrap())
                 && self.square_starting_point.x == -1.0
                 && self.square_starting_point.y == -1.0
             {
-                self.shift_squares = Some(Pos2::new(
-                    response.rect.left_top().x,
-                    response.rect.left_top().y,
-                ));
-
                 self.square_starting_point = pos.unwrap();
             }
         }
@@ -755,7 +1034,7 @@ impl Painting {
                         Rect::from_two_pos(self.square_starting_point, pos_dinamica.unwrap()),
                         Rounding::default(),
                         Color32::TRANSPARENT,
-                        Stroke::new(0.5, self.squares_color),
+                        self.style.screen_stroke(self.pixels_per_point().x),
                     )));
             }
         }
@@ -781,7 +1060,7 @@ impl Painting {
             let re =
                 egui::Rect::from_points(&[self.square_starting_point, self.square_ending_point]);
             if re.area() > 0.0 && re.width() > 0.0 && re.height() > 0.0 {
-                self.squares.push((re, self.squares_color));
+                self.squares.push((re, self.style));
                 self.last_type_added.push(PpOptions::Square);
             }
 
@@ -793,55 +1072,25 @@ impl Painting {
 
         self.render_elements(painter.clone(), to_screen);
 
-        let mut sqrs = Vec::new();
-  
-            for s in self.squares.clone() {
-                let min = Pos2::new(
-                    (s.0.left_top().x - self.shift_squares.unwrap().x)
-                        * self.mult_factor.unwrap().0,
-                    (s.0.left_top().y - self.shift_squares.unwrap().y)
-                        * self.mult_factor.unwrap().1,
-                );
-                let max = Pos2::new(
-                    (s.0.right_bottom().x - self.shift_squares.unwrap().x)
-                        * self.mult_factor.unwrap().0,
-                    (s.0.right_bottom().y - self.shift_squares.unwrap().y)
-                        * self.mult_factor.unwrap().1,
-                );
-
-                let r = egui::Rect::from_min_max(min, max);
-
-                sqrs.push((r, s.1));
-            }
-        
-        (Some(sqrs.clone()), Some(response))
+        (Some(self.squares_in_pixels(mapping)), Some(response))
     }
 
     pub fn ui_content_texts(
         &mut self,
         ui: &mut Ui,
         image: egui::Image,
-        mult_fact: &mut Option<(f32, f32)>,
         dim: Vec2,
         cut_clicked: bool,
-    ) -> (Option<Vec<(Pos2, Color32, String)>>, Option<Response>) {
-        let (response, painter) = ui.allocate_painter(dim, Sense::drag());
+    ) -> (Option<Vec<(Pos2, Style, String)>>, Option<Response>) {
+        let (response, painter) = ui.allocate_painter(dim, self.canvas_sense());
+        self.follow_canvas(response.rect);
 
         let to_screen = emath::RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
             response.rect,
         );
         image.paint_at(ui, response.rect);
-        self.shift_squares = Some(Pos2::new(
-            response.rect.left_top().x,
-            response.rect.left_top().y,
-        ));
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
-
-        *mult_fact = self.mult_factor;
+        let mapping = self.map_canvas(response.rect, &image);
         let mouse_pos = ui.input(|i| i.pointer.interact_pos());
         if mouse_pos.is_none() == false
             && response.rect.x_range().contains(mouse_pos.unwrap().x)
@@ -852,19 +1101,12 @@ impl Painting {
         self.render_elements(painter.clone(), to_screen);
 
         if ui.input(|i| i.pointer.any_pressed()) && cut_clicked == false {
+            //until Write! is pressed, every click moves the text
             let pos = ui.input(|i| i.pointer.interact_pos());
-            if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
-                && self.text_starting_position.x == -1.0
-                && self.text_starting_position.y == -1.0
+            if let Some(pos) =
+                pos.filter(|p| response.rect.intersect(painter.clip_rect()).contains(*p))
             {
-                self.text_starting_position = pos.unwrap();
-                self.inizializzato = true;
-            } else if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
-                && self.inizializzato == true
-            {
-                self.text_starting_position = pos.unwrap();
+                self.text_starting_position = pos;
             }
         }
 
@@ -872,50 +1114,26 @@ impl Painting {
             && self.text_starting_position.x != -1.0
             && self.text_starting_position.y != -1.0
         {
-            self.counter = self.counter + 1;
-            if self.counter == 3000 {
-                self.counter = 0;
-            }
-
-            if self.counter % 50 == 0 {
-                ui.painter().add(egui::Shape::dashed_line(
-                    &[
-                        Pos2::new(
-                            self.text_starting_position.x,
-                            self.text_starting_position.y + 10.0,
-                        ),
-                        Pos2::new(
-                            self.text_starting_position.x,
-                            self.text_starting_position.y - 10.0,
-                        ),
-                    ],
-                    Stroke::new(5.0, Color32::WHITE),
-                    10.0,
-                    0.0,
-                ));
-            } else {
-                ui.painter().add(egui::Shape::dashed_line(
-                    &[
-                        Pos2::new(
-                            self.text_starting_position.x,
-                            self.text_starting_position.y + 10.0,
-                        ),
-                        Pos2::new(
-                            self.text_starting_position.x,
-                            self.text_starting_position.y - 10.0,
-                        ),
-                    ],
-                    Stroke::new(5.0, Color32::BLACK),
-                    10.0,
-                    0.0,
-                ));
-            }
+            //the caret blinks once a second
+            let on = ui.input(|i| i.time).fract() < 0.5;
+            let caret = self.text_starting_position;
+            ui.painter().add(egui::Shape::dashed_line(
+                &[caret + vec2(0.0, 10.0), caret - vec2(0.0, 10.0)],
+                Stroke::new(5.0, if on { Color32::WHITE } else { Color32::BLACK }),
+                10.0,
+                0.0,
+            ));
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_millis(250));
         }
 
         if ui.input(|i| i.pointer.any_released()) && cut_clicked == false {
             let pos = ui.input(|i| i.pointer.interact_pos());
             if pos.is_none() == false
-                && response.rect.contains(pos.unwrap())
+                && response
+                    .rect
+                    .intersect(painter.clip_rect())
+                    .contains(pos.unwrap())
                 && self.text_ending_position.x == -1.0
                 && self.text_ending_position.y == -1.0
                 && self.text_starting_position.x != -1.0
@@ -936,7 +1154,7 @@ impl Painting {
                     self.to_write_text.clone(),
                     self.text_starting_position,
                     self.text_ending_position,
-                    self.texts_color,
+                    self.style,
                 ));
 
                 self.text_starting_position.x = -1.0;
@@ -944,45 +1162,134 @@ impl Painting {
                 self.text_ending_position.x = -1.0;
                 self.text_ending_position.y = -1.0;
                 self.ready_to_write = false;
-                self.inizializzato = false;
                 self.last_type_added.push(PpOptions::Text);
             }
         }
 
         self.render_elements(painter.clone(), to_screen);
-        let mut txt = Vec::new();
-        for t in self.texts.clone() {
-            let new_pos = Pos2::new(
-                (t.1.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0,
-                (t.1.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1,
-            );
-            txt.push((new_pos, t.3, t.0));
+
+        (Some(self.texts_in_pixels(mapping)), Some(response))
+    }
+
+    pub fn ui_content_select(
+        &mut self,
+        ui: &mut Ui,
+        image: egui::Image,
+        dim: Vec2,
+        cut_clicked: bool,
+    ) -> Option<Response> {
+        let sense = if self.panning {
+            Sense::hover()
+        } else {
+            Sense::click()
+        };
+        let (response, painter) = ui.allocate_painter(dim, sense);
+        self.follow_canvas(response.rect);
+
+        let to_screen = emath::RectTransform::from_to(
+            Rect::from_min_size(Pos2::ZERO, response.rect.square_proportions()),
+            response.rect,
+        );
+
+        image.paint_at(ui, response.rect);
+        self.map_canvas(response.rect, &image);
+        self.render_elements(painter.clone(), to_screen);
+
+        if response.clicked() && !cut_clicked {
+            self.selected = response
+                .interact_pointer_pos()
+                .and_then(|p| self.shape_at(p, to_screen));
+        }
+        if let Some(bounds) = self.selected_bounds(to_screen) {
+            let bounds = bounds.expand(4.0);
+            painter.rect_stroke(bounds, 0.0, Stroke::new(3.0, Color32::BLACK));
+            painter.rect_stroke(bounds, 0.0, Stroke::new(1.0, Color32::WHITE));
+        }
+
+        Some(response)
+    }
+
+    //Size of a text on the canvas, from its font size
+    fn text_rect(&self, text: &(String, Pos2, Pos2, Style)) -> Rect {
+        let font = text.3.font_size / self.pixels_per_point().y;
+        //monospace glyphs are about 0.6 times as wide as high
+        Rect::from_min_size(
+            text.1,
+            vec2(text.0.chars().count() as f32 * font * 0.6, font),
+        )
+    }
+
+    //The topmost shape near `p`, shapes are drawn by kind and texts last
+    fn shape_at(&self, p: Pos2, to_screen: emath::RectTransform) -> Option<(PpOptions, usize)> {
+        let pixels_per_point = self.pixels_per_point().x;
+        let near = |style: &Style| 6.0 + style.width / pixels_per_point / 2.0;
+
+        if let Some(i) = self
+            .texts
+            .iter()
+            .rposition(|t| self.text_rect(t).expand(4.0).contains(p))
+        {
+            return Some((PpOptions::Text, i));
+        }
+        if let Some(i) = self.squares.iter().rposition(|s| {
+            let d = near(&s.1);
+            s.0.expand(d).contains(p) && (!s.0.shrink(d).contains(p) || s.1.fill_color().a() > 0)
+        }) {
+            return Some((PpOptions::Square, i));
+        }
+        if let Some(i) = self.circles.iter().rposition(|c| {
+            let distance = c.0.distance(p);
+            (distance - c.1).abs() <= near(&c.2) || (distance < c.1 && c.2.fill_color().a() > 0)
+        }) {
+            return Some((PpOptions::Circle, i));
+        }
+        if let Some(i) = self
+            .arrows
+            .iter()
+            .rposition(|a| distance_to_segment(p, a.0, a.1) <= near(&a.2))
+        {
+            return Some((PpOptions::Arrow, i));
         }
+        self.lines
+            .iter()
+            .rposition(|l| {
+                l.0.windows(2).any(|w| {
+                    distance_to_segment(p, to_screen * w[0], to_screen * w[1]) <= near(&l.1)
+                })
+            })
+            .map(|i| (PpOptions::Painting, i))
+    }
 
-        (Some(txt.clone()), Some(response))
+    fn selected_bounds(&self, to_screen: emath::RectTransform) -> Option<Rect> {
+        match &self.selected {
+            Some((PpOptions::Painting, i)) => self.lines.get(*i).map(|l| {
+                let points: Vec<Pos2> = l.0.iter().map(|p| to_screen * *p).collect();
+                Rect::from_points(&points)
+            }),
+            Some((PpOptions::Arrow, i)) => {
+                self.arrows.get(*i).map(|a| Rect::from_two_pos(a.0, a.1))
+            }
+            Some((PpOptions::Circle, i)) => self
+                .circles
+                .get(*i)
+                .map(|c| Rect::from_center_size(c.0, Vec2::splat(2.0 * c.1))),
+            Some((PpOptions::Square, i)) => self.squares.get(*i).map(|s| s.0),
+            Some((PpOptions::Text, i)) => self.texts.get(*i).map(|t| self.text_rect(t)),
+            _ => None,
+        }
     }
 
     pub fn ui_content_cut(
         &mut self,
         ui: &mut Ui,
         image: egui::Image,
-        mult_fact: &mut Option<(f32, f32)>,
         dim: Vec2,
-    ) -> (Option<Vec<(Pos2, Color32, String)>>, Option<Response>) {
+    ) -> (Option<Vec<(Pos2, Style, String)>>, Option<Response>) {
         let (response, _painter) = ui.allocate_painter(dim, Sense::drag());
-
+        self.follow_canvas(response.rect);
 
         image.paint_at(ui, response.rect);
-        self.shift_squares = Some(Pos2::new(
-            response.rect.left_top().x,
-            response.rect.left_top().y,
-        ));
-        self.mult_factor = Some((
-            image.size().unwrap().x as f32 / response.rect.width(),
-            image.size().unwrap().y as f32 / response.rect.height(),
-        ));
-
-        *mult_fact = self.mult_factor;
+        self.map_canvas(response.rect, &image);
         let mouse_pos = ui.input(|i| i.pointer.interact_pos());
         if mouse_pos.is_none() == false
             && response.rect.x_range().contains(mouse_pos.unwrap().x)
@@ -1002,31 +1309,75 @@ impl Painting {
         (None, Some(response))
     }
 
-    pub fn calc_pixels_arrow(&mut self, origin: Pos2, vec: Vec2) -> Vec<Pos2> {
-        let mut pixels = Vec::new();
-
-        let new_vec = Vec2 {
-            x: vec.x * self.mult_factor.unwrap().0,
-            y: vec.y * self.mult_factor.unwrap().1,
+    //lines are kept relative to the canvas they were drawn on
+    fn lines_in_pixels(&self, mapping: ImageMapping) -> Vec<(Vec<Pos2>, Style)> {
+        let Some(canvas) = self.last_canvas else {
+            return Vec::new();
         };
-        let new_origin = Pos2::new(
-            (origin.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0,
-            (origin.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1,
+        let to_screen = emath::RectTransform::from_to(
+            Rect::from_min_size(Pos2::ZERO, canvas.square_proportions()),
+            canvas,
         );
-        let rot = Rot2::from_angle(std::f32::consts::TAU / 10.0);
-        let tip_length = new_vec.length() / 4.0;
-
-        let tip = new_origin + new_vec;
-        let dir = vec.normalized();
+        self.lines
+            .iter()
+            .filter(|l| !l.0.is_empty())
+            .map(|l| {
+                (
+                    l.0.iter()
+                        .map(|p| mapping.to_image(to_screen * *p))
+                        .collect(),
+                    l.1,
+                )
+            })
+            .collect()
+    }
 
-        pixels.push([new_origin, tip]);
-        pixels.push([tip, tip - tip_length * (rot * dir)]);
-        pixels.push([tip, tip - tip_length * (rot.inverse() * dir)]);
+    //every shape in pixels of the image, for when more than the last kind changed
+    #[allow(clippy::type_complexity)]
+    fn all_in_pixels(
+        &self,
+        mapping: ImageMapping,
+    ) -> (
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Pos2, Style, String)>>,
+        Option<Vec<(Rect, Style)>>,
+        Option<Vec<(Pos2, f32, Style)>>,
+    ) {
+        (
+            Some(self.lines_in_pixels(mapping)),
+            Some(self.arrows_pixels.clone()),
+            Some(self.texts_in_pixels(mapping)),
+            Some(self.squares_in_pixels(mapping)),
+            Some(self.circles_in_pixels(mapping)),
+        )
+    }
 
-        pixels.concat()
+    fn circles_in_pixels(&self, mapping: ImageMapping) -> Vec<(Pos2, f32, Style)> {
+        self.circles
+            .iter()
+            .map(|c| (mapping.to_image(c.0), c.1 * mapping.scale().y, c.2))
+            .collect()
     }
 
+    fn squares_in_pixels(&self, mapping: ImageMapping) -> Vec<(Rect, Style)> {
+        self.squares
+            .iter()
+            .map(|s| {
+                (
+                    Rect::from_min_max(mapping.to_image(s.0.min), mapping.to_image(s.0.max)),
+                    s.1,
+                )
+            })
+            .collect()
+    }
 
+    fn texts_in_pixels(&self, mapping: ImageMapping) -> Vec<(Pos2, Style, String)> {
+        self.texts
+            .iter()
+            .map(|t| (mapping.to_image(t.1), t.3, t.0.clone()))
+            .collect()
+    }
 }
 
 impl Demo for Painting {
@@ -1040,17 +1391,16 @@ impl View for Painting {
         &mut self,
         ui: &mut Ui,
         image: egui::widgets::Image,
-        mult_fact: &mut Option<(f32, f32)>,
-        dim: Vec2,
+        full_size: Vec2,
         opt: PpOptions,
         save: bool,
         cut_clicked: bool,
     ) -> (
-        Option<Vec<(Vec<Pos2>, Color32)>>,
-        Option<Vec<(Vec<Pos2>, Color32)>>,
-        Option<Vec<(Pos2, Color32, String)>>,
-        Option<Vec<(Rect, Color32)>>,
-        Option<Vec<(Pos2, f32, Color32)>>,
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Vec<Pos2>, Style)>>,
+        Option<Vec<(Pos2, Style, String)>>,
+        Option<Vec<(Rect, Style)>>,
+        Option<Vec<(Pos2, f32, Style)>>,
         Option<Response>,
     ) {
         let mut pix = None;
@@ -1068,22 +1418,43 @@ impl View for Painting {
             self.circles.clear();
             self.squares.clear();
             self.texts.clear();
+            self.transforms.clear();
+            self.removed_transforms.clear();
+            self.last_type_removed
+                .retain(|t| !matches!(t, PpOptions::Transform));
         }
+        if save || !matches!(opt, PpOptions::Select) {
+            self.selected = None;
+        }
+
+        let shapes_before = self.shapes_count();
+        let tool = opt.clone();
 
         match opt {
             PpOptions::Painting => {
                 self.ui_control(ui, opt);
                 ui.label(RichText::new("Paint with your mouse/touch! If you want to clear all the painting, click the button Clear Painting").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
-                    if image.size().unwrap()[0] >= 1000.0 && image.size().unwrap()[1] <= 500.0 {
-                        ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
-                            egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                                (pix, response) = self.ui_content(ui, image, dim, cut_clicked);
+                    let image_size = image.size().unwrap();
+                    if image_size[0] >= 1000.0 && image_size[1] <= 500.0 {
+                        ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+                            self.zoomable_canvas(ui, image_size, full_size, |painting, ui, dim| {
+                                (pix, response) = painting.ui_content(
+                                    ui,
+                                    image,
+                                    dim,
+                                    cut_clicked || painting.panning || painting.eyedropper,
+                                );
                             });
                         });
                     } else {
-                        egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                            (pix, response) = self.ui_content(ui, image, dim, cut_clicked);
+                        self.zoomable_canvas(ui, image_size, full_size, |painting, ui, dim| {
+                            (pix, response) = painting.ui_content(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
                         });
                     }
                 });
@@ -1092,164 +1463,180 @@ impl View for Painting {
                 self.ui_control(ui, opt);
                 ui.label(RichText::new("Paint an arrow with your mouse/touch! Press the left button of your mouse wherever you want, as a starting point, and release it when you want to finish drawing the arrow ").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
-                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                        (arr, response) = self.ui_content_arrows(ui, image, dim, cut_clicked);
-                    });
+                    self.zoomable_canvas(
+                        ui,
+                        image.size().unwrap(),
+                        full_size,
+                        |painting, ui, dim| {
+                            (arr, response) = painting.ui_content_arrows(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
+                        },
+                    );
                 });
             }
             PpOptions::Circle => {
                 self.ui_control(ui, opt);
                 ui.label(RichText::new("Paint a circle with your mouse/touch! Press the left button of your mouse wherever you want, to identify the circle's center, and release it when you want to finish drawing the circle").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
-                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                        (crcls, response) = self.ui_content_circles(ui, image, dim, cut_clicked);
-                    });
+                    self.zoomable_canvas(
+                        ui,
+                        image.size().unwrap(),
+                        full_size,
+                        |painting, ui, dim| {
+                            (crcls, response) = painting.ui_content_circles(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
+                        },
+                    );
                 });
             }
             PpOptions::Square => {
                 self.ui_control(ui, opt);
                 ui.label(RichText::new("Paint a square with your mouse/touch! Press the left button of your mouse wherever you want, to identify the rectangle's top-left corner, and release it when you want to set the right-bottom corner").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
-                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                        (sqrs, response) = self.ui_content_squares(ui, image, dim, cut_clicked);
-                    });
+                    self.zoomable_canvas(
+                        ui,
+                        image.size().unwrap(),
+                        full_size,
+                        |painting, ui, dim| {
+                            (sqrs, response) = painting.ui_content_squares(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
+                        },
+                    );
                 });
             }
             PpOptions::Text => {
                 self.ui_control(ui, opt);
                 ui.label(RichText::new("First, click were you want to write and type your text in the bar above! When you finish writing, press the button Write! to insert your text on the image below").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
-                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                        (txt, response) =
-                            self.ui_content_texts(ui, image, mult_fact, dim, cut_clicked);
-                    });
+                    self.zoomable_canvas(
+                        ui,
+                        image.size().unwrap(),
+                        full_size,
+                        |painting, ui, dim| {
+                            (txt, response) = painting.ui_content_texts(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
+                        },
+                    );
                 });
             }
 
             PpOptions::Cut => {
+                //the crop is edited on the whole image fitted in the window
+                self.zoom = 1.0;
+                self.panning = false;
                 self.ui_control(ui, opt);
-                ui.label(RichText::new("Restrict the grabbed image however you want and when you identify the right area to cut, press the button Finish Your Cut ").color(Color32::WHITE));
+                ui.label(RichText::new("Drag the handles or type the area to keep, then press Apply crop. Nothing outside is lost: cut again to widen the crop later").color(Color32::WHITE));
                 ui.vertical_centered(|ui| {
+                    let dim = Self::fit_canvas(ui, full_size, 0.0);
                     egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                        (txt, response) =
-                            self.ui_content_cut(ui, image, mult_fact, dim);
+                        (txt, response) = self.ui_content_cut(ui, image, dim);
                     });
                 });
             }
-
-                
+            PpOptions::Select => {
+                self.ui_control(ui, opt);
+                ui.label(
+                    RichText::new(
+                        "Click a shape to select it, then change its style with the toolbar above",
+                    )
+                    .color(Color32::WHITE),
+                );
+                ui.vertical_centered(|ui| {
+                    self.zoomable_canvas(
+                        ui,
+                        image.size().unwrap(),
+                        full_size,
+                        |painting, ui, dim| {
+                            response = painting.ui_content_select(
+                                ui,
+                                image,
+                                dim,
+                                cut_clicked || painting.panning || painting.eyedropper,
+                            );
+                        },
+                    );
+                });
+            }
+            PpOptions::Transform => {}
         }
-        if self.last_type_removed.last().is_some() {
-            match self.last_type_removed.last().unwrap() {
-                PpOptions::Arrow => {
-                    arr = Some(self.arrows_pixels.clone());
-                }
-                PpOptions::Circle => {
-                    let mut circls: Vec<(Pos2, f32, Color32)> = Vec::new();
-                    for c in self.circles.clone() {
-                        let center_x =
-                            (c.0.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0;
-                        let center_y =
-                            (c.0.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1;
-                        let new_center = Pos2::new(center_x, center_y);
-                        let new_radius = c.1 * self.mult_factor.unwrap().1;
-                        circls.push((new_center, new_radius, c.2));
-                    }
-                    crcls = Some(circls.clone());
-                  }
-                PpOptions::Square => {
-                    let mut sqars = Vec::new();
-                    for s in self.squares.clone() {
-                        let min = Pos2::new(
-                            (s.0.left_top().x - self.shift_squares.unwrap().x)
-                                * self.mult_factor.unwrap().0,
-                            (s.0.left_top().y - self.shift_squares.unwrap().y)
-                                * self.mult_factor.unwrap().1,
-                        );
-                        let max = Pos2::new(
-                            (s.0.right_bottom().x - self.shift_squares.unwrap().x)
-                                * self.mult_factor.unwrap().0,
-                            (s.0.right_bottom().y - self.shift_squares.unwrap().y)
-                                * self.mult_factor.unwrap().1,
-                        );
-
-                        let r = egui::Rect::from_min_max(min, max);
-
-                        sqars.push((r, s.1));
+        //nothing is in pixels until a canvas has shown the image
+        if let Some(mapping) = self.canvas_mapping {
+            let changed = [self.last_type_removed.last(), self.last_type_added.last()];
+            for kind in changed.into_iter().flatten().cloned().collect::<Vec<_>>() {
+                match kind {
+                    PpOptions::Arrow => {
+                        arr = Some(self.arrows_pixels.clone());
                     }
-                    sqrs = Some(sqars.clone());
-                }
-                PpOptions::Text => {
-                    let mut tx = Vec::new();
-                    for t in self.texts.clone() {
-                        let new_pos = Pos2::new(
-                            (t.1.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0,
-                            (t.1.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1,
-                        );
-                        tx.push((new_pos, t.3, t.0));
+                    PpOptions::Circle => {
+                        crcls = Some(self.circles_in_pixels(mapping));
                     }
-                    txt = Some(tx.clone());
-                }
-                _ => {}
-            }
-   
-        }
-        if self.last_type_added.last().is_some() {
-            match self.last_type_added.last().unwrap() {
-                PpOptions::Arrow => {
-                    arr = Some(self.arrows_pixels.clone());
-                }
-                PpOptions::Circle => {
-                    let mut circls: Vec<(Pos2, f32, Color32)> = Vec::new();
-                    for c in self.circles.clone() {
-                        let center_x =
-                            (c.0.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0;
-                        let center_y =
-                            (c.0.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1;
-                        let new_center = Pos2::new(center_x, center_y);
-                        let new_radius = c.1 * self.mult_factor.unwrap().1;
-                        circls.push((new_center, new_radius, c.2));
+                    PpOptions::Square => {
+                        sqrs = Some(self.squares_in_pixels(mapping));
                     }
-                    crcls = Some(circls.clone());
-                  }
-                PpOptions::Square => {
-                    let mut sqars = Vec::new();
-                    for s in self.squares.clone() {
-                        let min = Pos2::new(
-                            (s.0.left_top().x - self.shift_squares.unwrap().x)
-                                * self.mult_factor.unwrap().0,
-                            (s.0.left_top().y - self.shift_squares.unwrap().y)
-                                * self.mult_factor.unwrap().1,
-                        );
-                        let max = Pos2::new(
-                            (s.0.right_bottom().x - self.shift_squares.unwrap().x)
-                                * self.mult_factor.unwrap().0,
-                            (s.0.right_bottom().y - self.shift_squares.unwrap().y)
-                                * self.mult_factor.unwrap().1,
-                        );
-
-                        let r = egui::Rect::from_min_max(min, max);
-
-                        sqars.push((r, s.1));
+                    PpOptions::Text => {
+                        txt = Some(self.texts_in_pixels(mapping));
                     }
-                    sqrs = Some(sqars.clone());
-                }
-                PpOptions::Text => {
-                    let mut tx = Vec::new();
-                    for t in self.texts.clone() {
-                        let new_pos = Pos2::new(
-                            (t.1.x - self.shift_squares.unwrap().x) * self.mult_factor.unwrap().0,
-                            (t.1.y - self.shift_squares.unwrap().y) * self.mult_factor.unwrap().1,
-                        );
-                        tx.push((new_pos, t.3, t.0));
+                    PpOptions::Transform => {
+                        //every kind of shape may have moved
+                        (pix, arr, txt, sqrs, crcls) = self.all_in_pixels(mapping);
                     }
-                    txt = Some(tx.clone());
+                    _ => {}
                 }
-                _ => {}
             }
-   
+            if std::mem::take(&mut self.restyled) {
+                (pix, arr, txt, sqrs, crcls) = self.all_in_pixels(mapping);
+            }
+        }
+        if self.shapes_count() > shapes_before {
+            if let Some(color) = self.color_of(&tool) {
+                self.remember_color(color);
+            }
         }
-        *mult_fact = self.mult_factor;
         (pix, arr, txt, sqrs, crcls, response)
     }
 }
+
+//The three segments of an arrow drawn from `origin` along `vec`, in pixels of the image
+fn arrow_in_pixels(mapping: ImageMapping, origin: Pos2, vec: Vec2) -> Vec<Pos2> {
+    let origin = mapping.to_image(origin);
+    let vec = vec * mapping.scale();
+    let rot = Rot2::from_angle(std::f32::consts::TAU / 10.0);
+    let tip_length = vec.length() / 4.0;
+    let tip = origin + vec;
+    let dir = vec.normalized();
+
+    vec![
+        origin,
+        tip,
+        tip,
+        tip - tip_length * (rot * dir),
+        tip,
+        tip - tip_length * (rot.inverse() * dir),
+    ]
+}
+
+fn distance_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 {
+        ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + t * ab)
+}
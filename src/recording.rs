@@ -0,0 +1,449 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+
+//frames are kept in memory until the recording stops, past this size it stops by itself
+pub const MAX_BUFFER_BYTES: usize = 1 << 30;
+//1 is the best and slowest palette quantization, 30 the worst and fastest
+const GIF_QUANTIZATION_SPEED: i32 = 10;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RecordingFormat {
+    Gif,
+    Apng,
+}
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Gif => "gif",
+            RecordingFormat::Apng => "png",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordingFormat::Gif => "GIF",
+            RecordingFormat::Apng => "APNG",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordingSettings {
+    pub enabled: bool,
+    pub fps: u32,
+    pub max_seconds: u64,
+    pub format: RecordingFormat,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        RecordingSettings {
+            enabled: false,
+            fps: 10,
+            max_seconds: 30,
+            format: RecordingFormat::Gif,
+        }
+    }
+}
+
+pub struct Recording {
+    settings: RecordingSettings,
+    started: Instant,
+    next_frame: Instant,
+    frames: Vec<RgbaImage>,
+    buffered_bytes: usize,
+    max_bytes: usize,
+    //pixels (x, y, width, height) covered by the recording indicator, they keep
+    //the content of the first frame so the indicator never ends up in the recording
+    hidden: Option<(u32, u32, u32, u32)>,
+}
+
+impl Recording {
+    pub fn new(settings: RecordingSettings) -> Self {
+        let now = Instant::now();
+        Recording {
+            settings,
+            started: now,
+            next_frame: now,
+            frames: Vec::new(),
+            buffered_bytes: 0,
+            max_bytes: MAX_BUFFER_BYTES,
+            hidden: None,
+        }
+    }
+
+    pub fn hide_area(&mut self, area: (u32, u32, u32, u32)) {
+        self.hidden = Some(area);
+    }
+
+    pub fn get_settings(&self) -> RecordingSettings {
+        self.settings
+    }
+
+    pub fn is_due(&self) -> bool {
+        Instant::now() >= self.next_frame
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    //the max duration and the memory used by the frames both stop the recording
+    pub fn is_over(&self) -> bool {
+        self.elapsed() >= Duration::from_secs(self.settings.max_seconds)
+            || self.buffered_bytes >= self.max_bytes
+    }
+
+    pub fn push(&mut self, mut frame: RgbaImage) {
+        let every = Duration::from_secs_f32(1.0 / self.settings.fps.max(1) as f32);
+        self.next_frame += every;
+        //a slow capture drops frames instead of making them pile up
+        if self.next_frame <= Instant::now() {
+            self.next_frame = Instant::now() + every;
+        }
+        //the region does not change, a frame of another size is a failed capture
+        if self.frames.first().is_some_and(|f| f.dimensions() != frame.dimensions()) {
+            return;
+        }
+        if let (Some(first), Some((x, y, w, h))) = (self.frames.first(), self.hidden) {
+            let covered = image::imageops::crop_imm(first, x, y, w, h).to_image();
+            image::imageops::replace(&mut frame, &covered, x as i64, y as i64);
+        }
+        self.buffered_bytes += frame.as_raw().len();
+        self.frames.push(frame);
+    }
+
+    pub fn into_frames(self) -> Vec<RgbaImage> {
+        self.frames
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Gif(gif::EncodingError),
+    Png(png::EncodingError),
+    //GIF can not store images larger than 65535 pixels per side
+    TooLarge,
+    Empty,
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(e) => write!(f, "{}", e),
+            RecordingError::Gif(e) => write!(f, "{}", e),
+            RecordingError::Png(e) => write!(f, "{}", e),
+            RecordingError::TooLarge => write!(f, "the region is too large for a GIF"),
+            RecordingError::Empty => write!(f, "no frame has been recorded"),
+        }
+    }
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(e: std::io::Error) -> Self {
+        RecordingError::Io(e)
+    }
+}
+
+impl From<gif::EncodingError> for RecordingError {
+    fn from(e: gif::EncodingError) -> Self {
+        RecordingError::Gif(e)
+    }
+}
+
+impl From<png::EncodingError> for RecordingError {
+    fn from(e: png::EncodingError) -> Self {
+        RecordingError::Png(e)
+    }
+}
+
+pub fn encode(
+    frames: &[RgbaImage],
+    fps: u32,
+    format: RecordingFormat,
+    path: &Path,
+) -> Result<(), RecordingError> {
+    if frames.is_empty() {
+        return Err(RecordingError::Empty);
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        RecordingFormat::Gif => encode_gif(frames, fps, &mut writer)?,
+        RecordingFormat::Apng => encode_apng(frames, fps, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+//Every frame only stores the box of the pixels changed since the previous one,
+//the unchanged pixels inside it are transparent so the previous frame shows through
+fn encode_gif<W: Write>(frames: &[RgbaImage], fps: u32, writer: W) -> Result<(), RecordingError> {
+    let (width, height) = frames[0].dimensions();
+    let width: u16 = width.try_into().map_err(|_| RecordingError::TooLarge)?;
+    let height: u16 = height.try_into().map_err(|_| RecordingError::TooLarge)?;
+    //GIF delays are in hundredths of second and browsers slow down those under 2
+    let delay = (100 / fps.max(1)).max(2) as u16;
+
+    //(pixels, left, top, delay) of every frame to write
+    let mut parts: Vec<(RgbaImage, u32, u32, u16)> = vec![(frames[0].clone(), 0, 0, delay)];
+    for pair in frames.windows(2) {
+        match changed_box(&pair[0], &pair[1]) {
+            Some((left, top, w, h)) => {
+                let part = RgbaImage::from_fn(w, h, |x, y| {
+                    let p = *pair[1].get_pixel(left + x, top + y);
+                    if p == *pair[0].get_pixel(left + x, top + y) {
+                        image::Rgba([0, 0, 0, 0])
+                    } else {
+                        image::Rgba([p[0], p[1], p[2], 255])
+                    }
+                });
+                parts.push((part, left, top, delay));
+            }
+            //nothing changed, the previous frame simply stays longer
+            None => {
+                let last = parts.last_mut().unwrap();
+                last.3 = last.3.saturating_add(delay);
+            }
+        }
+    }
+
+    let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for (mut pixels, left, top, delay) in parts {
+        let mut frame = gif::Frame::from_rgba_speed(
+            pixels.width() as u16,
+            pixels.height() as u16,
+            &mut pixels,
+            GIF_QUANTIZATION_SPEED,
+        );
+        frame.left = left as u16;
+        frame.top = top as u16;
+        frame.delay = delay;
+        frame.dispose = gif::DisposalMethod::Keep;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+fn encode_apng<W: Write>(frames: &[RgbaImage], fps: u32, writer: W) -> Result<(), RecordingError> {
+    let (width, height) = frames[0].dimensions();
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(1, fps.clamp(1, u16::MAX as u32) as u16)?;
+
+    let mut png_writer = encoder.write_header()?;
+    for frame in frames {
+        png_writer.write_image_data(frame.as_raw())?;
+    }
+    png_writer.finish()?;
+    Ok(())
+}
+
+//Smallest box (left, top, width, height) containing every pixel that differs
+fn changed_box(previous: &RgbaImage, next: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let mut min_x = u32::MAX;
+    let mut min_y = u32::MAX;
+    let mut max_x = 0;
+    let mut max_y = 0;
+    for (x, y, p) in next.enumerate_pixels() {
+        if p != previous.get_pixel(x, y) {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    if min_x == u32::MAX {
+        return None;
+    }
+    Some((min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::path::PathBuf;
+
+    const RED: Rgba<u8> = Rgba([220, 20, 20, 255]);
+    const BLUE: Rgba<u8> = Rgba([20, 20, 220, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("recording_{}_{}", std::process::id(), name))
+    }
+
+    fn settings() -> RecordingSettings {
+        RecordingSettings {
+            enabled: true,
+            fps: 10,
+            max_seconds: 3600,
+            format: RecordingFormat::Gif,
+        }
+    }
+
+    //a white frame with a red square, moved right by `step` pixels
+    fn frame(step: u32) -> RgbaImage {
+        RgbaImage::from_fn(16, 12, |x, y| {
+            if (2 + step..6 + step).contains(&x) && (3..7).contains(&y) {
+                RED
+            } else {
+                WHITE
+            }
+        })
+    }
+
+    //the quantization may move a color by a few levels
+    fn close(a: &Rgba<u8>, b: &Rgba<u8>) -> bool {
+        a.0.iter().zip(b.0.iter()).all(|(a, b)| a.abs_diff(*b) <= 8)
+    }
+
+    //(left, top, width, height, delay, pixels) of every frame of a GIF
+    fn decode_gif(bytes: &[u8]) -> Vec<(u32, u32, u32, u32, u16, RgbaImage)> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(bytes).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let (width, height) = (frame.width as u32, frame.height as u32);
+            let pixels = RgbaImage::from_raw(width, height, frame.buffer.to_vec()).unwrap();
+            frames.push((frame.left as u32, frame.top as u32, width, height, frame.delay, pixels));
+        }
+        frames
+    }
+
+    #[test]
+    fn changed_box_bounds_the_differences() {
+        let previous = frame(0);
+        assert_eq!(changed_box(&previous, &previous), None);
+
+        let mut next = previous.clone();
+        next.put_pixel(9, 1, BLUE);
+        assert_eq!(changed_box(&previous, &next), Some((9, 1, 1, 1)));
+        next.put_pixel(0, 11, BLUE);
+        next.put_pixel(15, 4, BLUE);
+        assert_eq!(changed_box(&previous, &next), Some((0, 1, 16, 11)));
+
+        //the square moving by one pixel changes its first and last column only
+        assert_eq!(changed_box(&frame(0), &frame(1)), Some((2, 3, 5, 4)));
+    }
+
+    #[test]
+    fn gif_frames_store_only_the_changes() {
+        let frames = [frame(0), frame(1), frame(1), frame(3)];
+        let mut bytes = Vec::new();
+        encode_gif(&frames, 10, &mut bytes).unwrap();
+        let decoded = decode_gif(&bytes);
+
+        //the identical third frame makes the second one last twice as long
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded.iter().map(|f| f.4).collect::<Vec<_>>(), [10, 20, 10]);
+        assert_eq!(decoded.iter().map(|f| (f.0, f.1, f.2, f.3)).collect::<Vec<_>>(), [(0, 0, 16, 12), (2, 3, 5, 4), (3, 3, 6, 4)]);
+
+        //inside the box only the changed pixels are opaque
+        let (left, top, _, _, _, part) = &decoded[1];
+        for (x, y, p) in part.enumerate_pixels() {
+            let (x, y) = (left + x, top + y);
+            if frames[0].get_pixel(x, y) == frames[1].get_pixel(x, y) {
+                assert_eq!(p[3], 0, "unchanged pixel {},{} is not transparent", x, y);
+            } else {
+                assert!(close(p, frames[1].get_pixel(x, y)), "pixel {},{} is {:?}", x, y, p);
+            }
+        }
+
+        //drawn one over the other they give back every recorded frame
+        let mut shown = RgbaImage::new(16, 12);
+        for ((left, top, _, _, _, part), expected) in decoded.iter().zip([&frames[0], &frames[1], &frames[3]]) {
+            for (x, y, p) in part.enumerate_pixels() {
+                if p[3] != 0 {
+                    shown.put_pixel(left + x, top + y, *p);
+                }
+            }
+            for (x, y, p) in shown.enumerate_pixels() {
+                assert!(close(p, expected.get_pixel(x, y)), "pixel {},{} is {:?}", x, y, p);
+            }
+        }
+    }
+
+    #[test]
+    fn apng_keeps_every_frame() {
+        let frames = [frame(0), frame(1), frame(1), frame(3)];
+        let path = temp_path("frames.png");
+        encode(&frames, 25, RecordingFormat::Apng, &path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 4);
+        assert_eq!(control.num_plays, 0);
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        for expected in &frames {
+            let info = reader.next_frame(&mut buffer).unwrap();
+            let delay = reader.info().frame_control.unwrap();
+            assert_eq!((delay.delay_num, delay.delay_den), (1, 25));
+            assert_eq!(&buffer[..info.buffer_size()], expected.as_raw().as_slice());
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn encode_refuses_what_it_can_not_store() {
+        let path = temp_path("refused.gif");
+        assert!(matches!(encode(&[], 10, RecordingFormat::Gif, &path), Err(RecordingError::Empty)));
+        let wide = RgbaImage::new(u16::MAX as u32 + 1, 1);
+        assert!(matches!(encode(&[wide], 10, RecordingFormat::Gif, &path), Err(RecordingError::TooLarge)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn full_buffer_stops_the_recording() {
+        let frame_bytes = frame(0).as_raw().len();
+        let mut recording = Recording {
+            max_bytes: 3 * frame_bytes,
+            ..Recording::new(settings())
+        };
+        recording.push(frame(0));
+        recording.push(frame(1));
+        assert!(!recording.is_over());
+        //a frame of another size is a failed capture and takes no room
+        recording.push(RgbaImage::new(4, 4));
+        assert_eq!(recording.frame_count(), 2);
+        assert!(!recording.is_over());
+        recording.push(frame(2));
+        assert!(recording.is_over());
+        assert_eq!(Recording::new(settings()).max_bytes, MAX_BUFFER_BYTES);
+    }
+
+    #[test]
+    fn hidden_area_keeps_the_first_frame() {
+        let mut recording = Recording::new(settings());
+        recording.hide_area((0, 0, 4, 5));
+        recording.push(frame(0));
+        let mut indicator = frame(3);
+        for x in 0..4 {
+            for y in 0..5 {
+                indicator.put_pixel(x, y, BLUE);
+            }
+        }
+        recording.push(indicator);
+
+        let frames = recording.into_frames();
+        for (x, y, p) in frames[1].enumerate_pixels() {
+            let expected = if x < 4 && y < 5 { frame(0) } else { frame(3) };
+            assert_eq!(p, expected.get_pixel(x, y), "pixel {},{}", x, y);
+        }
+    }
+}
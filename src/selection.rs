@@ -0,0 +1,405 @@
+use egui::{CursorIcon, Key, PointerButton, Pos2, Rect, Vec2};
+
+//half of the side of the squares drawn on the handles, also used as grab tolerance
+pub const HANDLE_RADIUS: f32 = 5.0;
+//a selection smaller than this is considered a click and not a rectangle
+const MIN_SIZE: f32 = 2.0;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    Inside,
+}
+
+impl Handle {
+    pub fn cursor(&self) -> CursorIcon {
+        match self {
+            Handle::TopLeft | Handle::BottomRight => CursorIcon::ResizeNwSe,
+            Handle::TopRight | Handle::BottomLeft => CursorIcon::ResizeNeSw,
+            Handle::Top | Handle::Bottom => CursorIcon::ResizeVertical,
+            Handle::Left | Handle::Right => CursorIcon::ResizeHorizontal,
+            Handle::Inside => CursorIcon::Move,
+        }
+    }
+
+    fn moves_left(&self) -> bool {
+        matches!(self, Handle::TopLeft | Handle::Left | Handle::BottomLeft)
+    }
+
+    fn moves_right(&self) -> bool {
+        matches!(self, Handle::TopRight | Handle::Right | Handle::BottomRight)
+    }
+
+    fn moves_top(&self) -> bool {
+        matches!(self, Handle::TopLeft | Handle::Top | Handle::TopRight)
+    }
+
+    fn moves_bottom(&self) -> bool {
+        matches!(self, Handle::BottomLeft | Handle::Bottom | Handle::BottomRight)
+    }
+}
+
+//Constraints the user can choose for the rectangle before selecting it,
+//sizes are in physical pixels
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Preset {
+    Free,
+    Size(u32, u32),
+    Ratio(u32, u32),
+}
+
+impl Preset {
+    pub fn label(&self) -> String {
+        match self {
+            Preset::Free => "Free".to_string(),
+            Preset::Size(w, h) => format!("{} × {}", w, h),
+            Preset::Ratio(w, h) => format!("{}:{}", w, h),
+        }
+    }
+}
+
+pub const PRESETS: [Preset; 8] = [
+    Preset::Free,
+    Preset::Size(1280, 720),
+    Preset::Size(1920, 1080),
+    Preset::Size(800, 600),
+    Preset::Ratio(16, 9),
+    Preset::Ratio(4, 3),
+    Preset::Ratio(1, 1),
+    Preset::Ratio(9, 16),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Drag {
+    //a new rectangle is being drawn from the anchor point
+    Create(Pos2),
+    Resize(Handle, Rect),
+    Move(Pos2, Rect),
+}
+
+//The rectangle selected in the overlay: it can be drawn, resized from its eight
+//handles, moved and nudged with the keyboard until the capture is confirmed
+#[derive(Debug, Default)]
+pub struct Selection {
+    rect: Option<Rect>,
+    drag: Option<Drag>,
+    //logical size of the rectangle when the preset fixes it
+    fixed_size: Option<Vec2>,
+    //width / height imposed by the preset
+    ratio: Option<f32>,
+}
+
+impl Selection {
+    pub fn new(preset: Preset, pixels_per_point: f32) -> Self {
+        let (fixed_size, ratio) = match preset {
+            Preset::Free => (None, None),
+            Preset::Size(w, h) => (Some(Vec2::new(w as f32, h as f32) / pixels_per_point), None),
+            Preset::Ratio(w, h) => (None, Some(w as f32 / h as f32)),
+        };
+        Selection {
+            rect: None,
+            drag: None,
+            fixed_size,
+            ratio,
+        }
+    }
+
+    //a rectangle with a fixed size can only be moved
+    pub fn is_resizable(&self) -> bool {
+        self.fixed_size.is_none()
+    }
+
+    pub fn get_rect(&self) -> Option<Rect> {
+        self.rect
+            .filter(|r| r.width() >= MIN_SIZE && r.height() >= MIN_SIZE)
+    }
+
+    pub fn handle_rects(rect: Rect) -> [(Handle, Rect); 8] {
+        let square = |p: Pos2| Rect::from_center_size(p, Vec2::splat(HANDLE_RADIUS * 2.0));
+        [
+            (Handle::TopLeft, square(rect.left_top())),
+            (Handle::Top, square(rect.center_top())),
+            (Handle::TopRight, square(rect.right_top())),
+            (Handle::Right, square(rect.right_center())),
+            (Handle::BottomRight, square(rect.right_bottom())),
+            (Handle::Bottom, square(rect.center_bottom())),
+            (Handle::BottomLeft, square(rect.left_bottom())),
+            (Handle::Left, square(rect.left_center())),
+        ]
+    }
+
+    //What is under the pointer: one of the handles, the inside of the rectangle or nothing
+    pub fn handle_at(&self, pos: Pos2) -> Option<Handle> {
+        let rect = self.get_rect()?;
+        if !self.is_resizable() {
+            return if rect.contains(pos) { Some(Handle::Inside) } else { None };
+        }
+        Self::handle_rects(rect)
+            .iter()
+            .find(|(_, r)| r.expand(HANDLE_RADIUS).contains(pos))
+            .map(|(h, _)| *h)
+            .or(if rect.contains(pos) {
+                Some(Handle::Inside)
+            } else {
+                None
+            })
+    }
+
+    pub fn cursor_at(&self, pos: Pos2) -> CursorIcon {
+        match self.drag {
+            Some(Drag::Resize(handle, _)) => handle.cursor(),
+            Some(Drag::Move(..)) => CursorIcon::Grabbing,
+            Some(Drag::Create(_)) => CursorIcon::Crosshair,
+            None => self
+                .handle_at(pos)
+                .map_or(CursorIcon::Crosshair, |h| h.cursor()),
+        }
+    }
+
+    pub fn begin_drag(&mut self, pos: Pos2, bounds: Rect) {
+        self.drag = match (self.handle_at(pos), self.rect) {
+            (Some(Handle::Inside), Some(rect)) => Some(Drag::Move(pos, rect)),
+            (Some(handle), Some(rect)) => Some(Drag::Resize(handle, rect)),
+            _ => match self.fixed_size {
+                //the rectangle is placed where the user clicks and then dragged around,
+                //shrunk like in set_rect when the bounds are too small for it
+                Some(size) => {
+                    let fit = (bounds.width() / size.x).min(bounds.height() / size.y).min(1.0);
+                    let rect = move_inside(Rect::from_min_size(pos, size * fit), Vec2::ZERO, bounds);
+                    self.rect = Some(rect);
+                    Some(Drag::Move(pos, rect))
+                }
+                None => Some(Drag::Create(pos)),
+            },
+        };
+    }
+
+    //`keep_aspect` locks the proportions of the rectangle being resized and makes new
+    //rectangles squares, unless the preset already imposes a ratio
+    pub fn drag_to(&mut self, pos: Pos2, keep_aspect: bool, bounds: Rect) {
+        let pos = bounds.clamp(pos);
+        match self.drag {
+            Some(Drag::Create(anchor)) => {
+                let mut delta = pos - anchor;
+                let ratio = self.ratio.or(if keep_aspect { Some(1.0) } else { None });
+                if let Some(ratio) = ratio {
+                    let room_x = if delta.x < 0.0 { anchor.x - bounds.min.x } else { bounds.max.x - anchor.x };
+                    let room_y = if delta.y < 0.0 { anchor.y - bounds.min.y } else { bounds.max.y - anchor.y };
+                    let width = delta.x.abs().max(delta.y.abs() * ratio).min(room_x).min(room_y * ratio);
+                    delta = Vec2::new(width * delta.x.signum(), width / ratio * delta.y.signum());
+                }
+                self.rect = Some(Rect::from_two_pos(anchor, anchor + delta).intersect(bounds));
+            }
+            Some(Drag::Resize(handle, start)) => {
+                let mut rect = start;
+                if handle.moves_left() {
+                    rect.min.x = pos.x;
+                }
+                if handle.moves_right() {
+                    rect.max.x = pos.x;
+                }
+                if handle.moves_top() {
+                    rect.min.y = pos.y;
+                }
+                if handle.moves_bottom() {
+                    rect.max.y = pos.y;
+                }
+                let ratio = self.ratio.or(if keep_aspect && start.height() > 0.0 {
+                    Some(start.aspect_ratio())
+                } else {
+                    None
+                });
+                if let Some(ratio) = ratio {
+                    rect = keep_aspect_ratio(rect, handle, ratio, bounds);
+                }
+                //dragging a handle past the opposite side flips the rectangle
+                self.rect = Some(Rect::from_two_pos(rect.min, rect.max));
+            }
+            Some(Drag::Move(grab, start)) => {
+                self.rect = Some(move_inside(start, pos - grab, bounds));
+            }
+            None => {}
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn nudge(&mut self, delta: Vec2, bounds: Rect) {
+        if let Some(rect) = self.get_rect() {
+            self.rect = Some(move_inside(rect, delta, bounds));
+        }
+    }
+
+    //Places the rectangle where it is asked, as long as the preset allows it: the size
+    //follows the preset and a rectangle too large for the bounds is shrunk to fit
+    pub fn set_rect(&mut self, rect: Rect, bounds: Rect) {
+        let mut size = self.fixed_size.unwrap_or(rect.size());
+        if let Some(ratio) = self.ratio {
+            size.y = size.x / ratio;
+        }
+        if size.x < MIN_SIZE || size.y < MIN_SIZE {
+            return;
+        }
+        let fit = (bounds.width() / size.x).min(bounds.height() / size.y).min(1.0);
+        self.rect = Some(move_inside(Rect::from_min_size(rect.min, size * fit), Vec2::ZERO, bounds));
+    }
+
+    //Pointer drags and arrow keys (shift moves by 10 and keeps the aspect), drags start
+    //only inside the bounds. Tells whether the selection has been confirmed with
+    //Enter or a double-click inside it
+    pub fn handle_input(&mut self, ui: &egui::Ui, bounds: Rect) -> bool {
+        let (pointer, pressed, down, released, double_clicked, shift) = ui.input(|i| {
+            (
+                i.pointer.latest_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_down(),
+                i.pointer.primary_released(),
+                i.pointer.button_double_clicked(PointerButton::Primary),
+                i.modifiers.shift,
+            )
+        });
+
+        if let Some(pos) = pointer {
+            let inside = bounds.expand(HANDLE_RADIUS).contains(pos);
+            if pressed && inside {
+                self.begin_drag(pos, bounds);
+            }
+            if down {
+                self.drag_to(pos, shift, bounds);
+            }
+            if inside || self.drag.is_some() {
+                let cursor = self.cursor_at(pos);
+                ui.ctx().output_mut(|o| o.cursor_icon = cursor);
+            }
+        }
+        if released {
+            self.end_drag();
+        }
+
+        let step = if shift { 10.0 } else { 1.0 };
+        let nudge = ui.input(|i| {
+            let mut delta = Vec2::ZERO;
+            if i.key_pressed(Key::ArrowLeft) {
+                delta.x -= step;
+            }
+            if i.key_pressed(Key::ArrowRight) {
+                delta.x += step;
+            }
+            if i.key_pressed(Key::ArrowUp) {
+                delta.y -= step;
+            }
+            if i.key_pressed(Key::ArrowDown) {
+                delta.y += step;
+            }
+            delta
+        });
+        if nudge != Vec2::ZERO {
+            self.nudge(nudge, bounds);
+        }
+
+        ui.input(|i| i.key_pressed(Key::Enter))
+            || (double_clicked && pointer.is_some_and(|p| self.handle_at(p) == Some(Handle::Inside)))
+    }
+}
+
+//Moves the rectangle without letting it leave the bounds
+fn move_inside(rect: Rect, delta: Vec2, bounds: Rect) -> Rect {
+    let moved = rect.translate(delta);
+    let dx = (bounds.min.x - moved.min.x).max(0.0) + (bounds.max.x - moved.max.x).min(0.0);
+    let dy = (bounds.min.y - moved.min.y).max(0.0) + (bounds.max.y - moved.max.y).min(0.0);
+    moved.translate(Vec2::new(dx, dy))
+}
+
+//Adjusts the side the handle does not control so that width / height == ratio,
+//the corner opposite to the handle stays where it is. As when a rectangle is created,
+//the size is limited by the room left on both sides so that it stays inside the bounds
+fn keep_aspect_ratio(mut rect: Rect, handle: Handle, ratio: f32, bounds: Rect) -> Rect {
+    let width = rect.width().abs();
+    let height = rect.height().abs();
+    let width = match handle {
+        Handle::Top | Handle::Bottom => height * ratio,
+        Handle::Left | Handle::Right => width,
+        _ => width.max(height * ratio),
+    };
+    let sign_x = if rect.width() < 0.0 { -1.0 } else { 1.0 };
+    let sign_y = if rect.height() < 0.0 { -1.0 } else { 1.0 };
+
+    //the side that stays and the direction the other one goes from it
+    let (fixed_x, dir_x) = if handle.moves_left() { (rect.max.x, -sign_x) } else { (rect.min.x, sign_x) };
+    let (fixed_y, dir_y) = if handle.moves_top() { (rect.max.y, -sign_y) } else { (rect.min.y, sign_y) };
+    let room_x = if dir_x < 0.0 { fixed_x - bounds.min.x } else { bounds.max.x - fixed_x };
+    let room_y = if dir_y < 0.0 { fixed_y - bounds.min.y } else { bounds.max.y - fixed_y };
+    let width = width.min(room_x.max(0.0)).min(room_y.max(0.0) * ratio);
+    let height = width / ratio;
+
+    if handle.moves_left() {
+        rect.min.x = rect.max.x - width * sign_x;
+    } else {
+        rect.max.x = rect.min.x + width * sign_x;
+    }
+    if handle.moves_top() {
+        rect.min.y = rect.max.y - height * sign_y;
+    } else {
+        rect.max.y = rect.min.y + height * sign_y;
+    }
+    rect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> Rect {
+        Rect::from_min_size(Pos2::ZERO, Vec2::new(1000.0, 600.0))
+    }
+
+    fn assert_inside(rect: Rect) {
+        let area = bounds().expand(0.01);
+        assert!(area.contains(rect.min) && area.contains(rect.max), "{:?} leaves the bounds", rect);
+    }
+
+    #[test]
+    fn resizing_with_a_ratio_stops_at_the_edge() {
+        let mut selection = Selection::new(Preset::Ratio(16, 9), 1.0);
+        selection.set_rect(Rect::from_min_size(Pos2::new(100.0, 100.0), Vec2::new(160.0, 90.0)), bounds());
+
+        for (handle, target) in [
+            (Handle::BottomRight, Pos2::new(2000.0, 2000.0)),
+            (Handle::Right, Pos2::new(2000.0, 150.0)),
+            (Handle::Bottom, Pos2::new(150.0, 2000.0)),
+            (Handle::TopLeft, Pos2::new(-500.0, -500.0)),
+        ] {
+            let rect = selection.get_rect().unwrap();
+            let grab = Selection::handle_rects(rect).iter().find(|(h, _)| *h == handle).unwrap().1.center();
+            selection.begin_drag(grab, bounds());
+            selection.drag_to(target, false, bounds());
+            selection.end_drag();
+
+            let rect = selection.get_rect().unwrap();
+            assert_inside(rect);
+            assert!((rect.aspect_ratio() - 16.0 / 9.0).abs() < 0.01, "{:?} after {:?}", rect, handle);
+        }
+    }
+
+    #[test]
+    fn fixed_size_larger_than_the_bounds_is_shrunk() {
+        let mut selection = Selection::new(Preset::Size(1920, 1080), 1.0);
+
+        selection.begin_drag(Pos2::new(500.0, 300.0), bounds());
+        selection.drag_to(Pos2::new(600.0, 400.0), false, bounds());
+
+        let rect = selection.get_rect().unwrap();
+        assert_inside(rect);
+        assert!((rect.aspect_ratio() - 16.0 / 9.0).abs() < 0.01);
+        assert!((rect.width() - 1000.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use image::RgbaImage;
+
+//share of the informative overlapping rows that must match to accept an overlap
+const MIN_MATCH_RATIO: f32 = 0.9;
+//overlaps built only on few rows are too easy to match by chance
+const MIN_INFORMATIVE_ROWS: usize = 4;
+
+struct RowSignature {
+    hash: u64,
+    //rows made of a single color (blank page areas) say nothing about the scroll offset
+    uniform: bool,
+}
+
+fn row_signatures(img: &RgbaImage, width: u32) -> Vec<RowSignature> {
+    (0..img.height())
+        .map(|y| {
+            let first = img.get_pixel(0, y);
+            let mut hasher = DefaultHasher::new();
+            let mut uniform = true;
+            for x in 0..width {
+                let p = img.get_pixel(x, y);
+                p.0.hash(&mut hasher);
+                uniform = uniform && p == first;
+            }
+            RowSignature {
+                hash: hasher.finish(),
+                uniform,
+            }
+        })
+        .collect()
+}
+
+//Number of rows at the top of `next` that repeat the bottom of `previous`:
+//0 means the frames share nothing, previous.height() means nothing has scrolled
+pub fn find_vertical_overlap(previous: &RgbaImage, next: &RgbaImage) -> usize {
+    if previous == next {
+        return previous.height() as usize;
+    }
+    let width = previous.width().min(next.width());
+    if width == 0 {
+        return 0;
+    }
+    let prev_rows = row_signatures(previous, width);
+    let next_rows = row_signatures(next, width);
+    let max_overlap = prev_rows.len().min(next_rows.len());
+
+    let mut best: Option<(f32, usize)> = None;
+    for overlap in (1..=max_overlap).rev() {
+        let prev_part = &prev_rows[prev_rows.len() - overlap..];
+        let next_part = &next_rows[..overlap];
+
+        let mut informative = 0;
+        let mut matching = 0;
+        for (p, n) in prev_part.iter().zip(next_part.iter()) {
+            if p.uniform && n.uniform {
+                if p.hash != n.hash {
+                    //two blank rows of different colors can not be the same row
+                    informative += 1;
+                }
+                continue;
+            }
+            informative += 1;
+            if p.hash == n.hash {
+                matching += 1;
+            }
+        }
+        if informative < MIN_INFORMATIVE_ROWS.min(overlap) {
+            continue;
+        }
+
+        let ratio = matching as f32 / informative as f32;
+        //scanning from the biggest overlap, ties keep the biggest one
+        if ratio >= MIN_MATCH_RATIO && !matches!(best, Some((r, _)) if r >= ratio) {
+            best = Some((ratio, overlap));
+        }
+    }
+
+    best.map_or(0, |(_, overlap)| overlap)
+}
+
+//Joins the frames from top to bottom dropping the rows every frame shares with the previous one
+pub fn stitch_vertical(frames: &[RgbaImage]) -> Option<RgbaImage> {
+    let width = frames.iter().map(|f| f.width()).min()?;
+
+    let mut parts: Vec<(&RgbaImage, u32)> = Vec::new();
+    let mut previous: Option<&RgbaImage> = None;
+    for frame in frames {
+        let skip = match previous {
+            Some(prev) => find_vertical_overlap(prev, frame) as u32,
+            None => 0,
+        };
+        if skip < frame.height() {
+            parts.push((frame, skip));
+            previous = Some(frame);
+        }
+    }
+
+    let height: u32 = parts.iter().map(|(f, skip)| f.height() - skip).sum();
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut stitched = RgbaImage::new(width, height);
+    let mut y_offset = 0;
+    for (frame, skip) in parts {
+        for y in skip..frame.height() {
+            for x in 0..width {
+                stitched.put_pixel(x, y_offset, *frame.get_pixel(x, y));
+            }
+            y_offset += 1;
+        }
+    }
+    Some(stitched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    //a page whose rows all differ, as the text of a document would
+    fn page(width: u32, height: u32, seed: u32) -> RgbaImage {
+        let mut state = seed.wrapping_mul(2654435761).max(1);
+        RgbaImage::from_fn(width, height, |_, _| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let [r, g, b, _] = state.to_le_bytes();
+            Rgba([r, g, b, 255])
+        })
+    }
+
+    fn crop(img: &RgbaImage, y: u32, height: u32) -> RgbaImage {
+        image::imageops::crop_imm(img, 0, y, img.width(), height).to_image()
+    }
+
+    fn blank(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]))
+    }
+
+    //`top` above `bottom`
+    fn stack(top: &RgbaImage, bottom: &RgbaImage) -> RgbaImage {
+        let mut out = RgbaImage::new(top.width(), top.height() + bottom.height());
+        image::imageops::replace(&mut out, top, 0, 0);
+        image::imageops::replace(&mut out, bottom, 0, top.height() as i64);
+        out
+    }
+
+    #[test]
+    fn overlapping_crops_rebuild_the_page() {
+        let page = page(32, 230, 1);
+        let frames = vec![crop(&page, 0, 100), crop(&page, 60, 100), crop(&page, 130, 100)];
+
+        assert_eq!(find_vertical_overlap(&frames[0], &frames[1]), 40);
+        assert_eq!(find_vertical_overlap(&frames[1], &frames[2]), 30);
+        assert!(stitch_vertical(&frames).unwrap() == page);
+    }
+
+    #[test]
+    fn frames_without_overlap_give_zero() {
+        let first = page(32, 80, 2);
+        let second = page(32, 80, 3);
+
+        assert_eq!(find_vertical_overlap(&first, &second), 0);
+        assert!(stitch_vertical(&[first.clone(), second.clone()]).unwrap() == stack(&first, &second));
+    }
+
+    #[test]
+    fn identical_frames_are_dropped() {
+        let page = page(32, 150, 4);
+        let top = crop(&page, 0, 100);
+        let bottom = crop(&page, 50, 100);
+
+        assert_eq!(find_vertical_overlap(&top, &top), 100);
+        let frames = vec![top.clone(), top.clone(), bottom.clone(), bottom];
+        assert!(stitch_vertical(&frames).unwrap() == page);
+    }
+
+    #[test]
+    fn blank_rows_do_not_match() {
+        //both frames touch on a white margin, but their content is unrelated
+        let first = stack(&page(32, 60, 5), &blank(32, 20));
+        let second = stack(&blank(32, 20), &page(32, 60, 6));
+
+        assert_eq!(find_vertical_overlap(&first, &second), 0);
+        assert_eq!(stitch_vertical(&[first, second]).unwrap().height(), 160);
+    }
+}
@@ -0,0 +1,179 @@
+use egui::{Color32, Pos2, Rect};
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::drawing;
+use rusttype::{Font, Scale};
+
+//How an annotation looks, shared by all the tools of the editor and kept with every
+//shape. Width and font size are in pixels of the image, so the shapes keep their
+//look whatever the zoom of the editor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub stroke: Color32,
+    //transparent when the shape is not filled
+    pub fill: Color32,
+    pub width: f32,
+    //from 0 to 1, applied on top of the alpha of both colors
+    pub opacity: f32,
+    pub font_size: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            stroke: Color32::from_rgba_unmultiplied(25, 200, 100, 255),
+            fill: Color32::TRANSPARENT,
+            width: 3.0,
+            opacity: 1.0,
+            font_size: 24.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn stroke_color(&self) -> Color32 {
+        with_opacity(self.stroke, self.opacity)
+    }
+
+    pub fn fill_color(&self) -> Color32 {
+        with_opacity(self.fill, self.opacity)
+    }
+
+    //the stroke seen in the editor, where a point shows `pixels_per_point` pixels of the image
+    pub fn screen_stroke(&self, pixels_per_point: f32) -> egui::Stroke {
+        egui::Stroke::new(self.width / pixels_per_point, self.stroke_color())
+    }
+}
+
+fn with_opacity(c: Color32, opacity: f32) -> Color32 {
+    let [r, g, b, a] = c.to_srgba_unmultiplied();
+    Color32::from_rgba_unmultiplied(r, g, b, (a as f32 * opacity.clamp(0.0, 1.0)).round() as u8)
+}
+
+fn to_rgba(c: Color32) -> Rgba<u8> {
+    Rgba(c.to_srgba_unmultiplied())
+}
+
+//Every shape is drawn opaque on a layer of its own and then blended on the image,
+//so that overlapping parts of a translucent stroke don't get darker
+fn burn(image: &mut RgbaImage, bounds: Rect, draw: impl FnOnce(&mut RgbaImage, Pos2)) {
+    let x0 = bounds.min.x.floor().max(0.0) as u32;
+    let y0 = bounds.min.y.floor().max(0.0) as u32;
+    let x1 = (bounds.max.x.ceil().max(0.0) as u32).min(image.width());
+    let y1 = (bounds.max.y.ceil().max(0.0) as u32).min(image.height());
+    if x1 <= x0 || y1 <= y0 {
+        return;
+    }
+    let mut layer = RgbaImage::new(x1 - x0, y1 - y0);
+    draw(&mut layer, Pos2::new(x0 as f32, y0 as f32));
+    imageops::overlay(image, &layer, x0 as i64, y0 as i64);
+}
+
+fn thick_segment(layer: &mut RgbaImage, a: Pos2, b: Pos2, width: f32, color: Rgba<u8>) {
+    if width <= 1.5 {
+        drawing::draw_line_segment_mut(layer, (a.x, a.y), (b.x, b.y), color);
+        return;
+    }
+    let radius = width / 2.0;
+    let cap = (radius.round() as i32).max(1);
+    drawing::draw_filled_circle_mut(layer, (a.x.round() as i32, a.y.round() as i32), cap, color);
+    drawing::draw_filled_circle_mut(layer, (b.x.round() as i32, b.y.round() as i32), cap, color);
+    let normal = (b - a).normalized().rot90() * radius;
+    let corners = [a + normal, b + normal, b - normal, a - normal]
+        .map(|p| imageproc::point::Point::new(p.x.round() as i32, p.y.round() as i32));
+    //a polygon whose first and last corners coincide can't be drawn
+    if corners[0] != corners[3] && corners[0] != corners[1] {
+        drawing::draw_polygon_mut(layer, &corners, color);
+    }
+}
+
+pub fn burn_polyline(image: &mut RgbaImage, points: &[Pos2], style: &Style) {
+    if points.is_empty() {
+        return;
+    }
+    let bounds = Rect::from_points(points).expand(style.width);
+    let color = to_rgba(style.stroke_color());
+    burn(image, bounds, |layer, origin| {
+        let local: Vec<Pos2> = points.iter().map(|p| *p - origin.to_vec2()).collect();
+        for pair in local.windows(2) {
+            thick_segment(layer, pair[0], pair[1], style.width, color);
+        }
+        if local.len() == 1 {
+            thick_segment(layer, local[0], local[0], style.width, color);
+        }
+    });
+}
+
+//`segments` are pairs of points, as the arrows come from the editor
+pub fn burn_segments(image: &mut RgbaImage, segments: &[(Pos2, Pos2)], style: &Style) {
+    let points: Vec<Pos2> = segments.iter().flat_map(|(a, b)| [*a, *b]).collect();
+    if points.is_empty() {
+        return;
+    }
+    let bounds = Rect::from_points(&points).expand(style.width);
+    let color = to_rgba(style.stroke_color());
+    burn(image, bounds, |layer, origin| {
+        for (a, b) in segments {
+            thick_segment(layer, *a - origin.to_vec2(), *b - origin.to_vec2(), style.width, color);
+        }
+    });
+}
+
+pub fn burn_rect(image: &mut RgbaImage, rect: Rect, style: &Style) {
+    burn(image, rect.expand(style.width), |layer, origin| {
+        let r = rect.translate(-origin.to_vec2());
+        if style.fill_color().a() > 0 {
+            let fill = imageproc::rect::Rect::at(r.min.x.round() as i32, r.min.y.round() as i32)
+                .of_size(r.width().round().max(1.0) as u32, r.height().round().max(1.0) as u32);
+            drawing::draw_filled_rect_mut(layer, fill, to_rgba(style.fill_color()));
+        }
+        let color = to_rgba(style.stroke_color());
+        let corners = [r.left_top(), r.right_top(), r.right_bottom(), r.left_bottom()];
+        for i in 0..4 {
+            thick_segment(layer, corners[i], corners[(i + 1) % 4], style.width, color);
+        }
+    });
+}
+
+pub fn burn_circle(image: &mut RgbaImage, center: Pos2, radius: f32, style: &Style) {
+    let bounds = Rect::from_center_size(center, egui::Vec2::splat(2.0 * radius)).expand(style.width);
+    burn(image, bounds, |layer, origin| {
+        let c = center - origin.to_vec2();
+        let c = (c.x.round() as i32, c.y.round() as i32);
+        let outer = (radius + style.width / 2.0).round() as i32;
+        let inner = (radius - style.width / 2.0).round() as i32;
+        if style.width <= 1.5 {
+            drawing::draw_hollow_circle_mut(layer, c, radius.round() as i32, to_rgba(style.stroke_color()));
+        } else {
+            drawing::draw_filled_circle_mut(layer, c, outer, to_rgba(style.stroke_color()));
+            //the layer is overwritten, not blended: this leaves a ring
+            if inner > 0 {
+                drawing::draw_filled_circle_mut(layer, c, inner, Rgba([0, 0, 0, 0]));
+            }
+        }
+        if style.fill_color().a() > 0 && inner > 0 {
+            drawing::draw_filled_circle_mut(layer, c, inner, to_rgba(style.fill_color()));
+        }
+    });
+}
+
+pub fn burn_text(image: &mut RgbaImage, position: Pos2, text: &str, style: &Style, font: &Font) {
+    let bounds = Rect::from_min_max(position, Pos2::new(image.width() as f32, image.height() as f32));
+    burn(image, bounds, |layer, origin| {
+        //glyph edges are mixed with the pixels under them: those get the color of the
+        //text and no alpha, so that the edges don't darken
+        let [r, g, b, _] = style.stroke_color().to_srgba_unmultiplied();
+        for pixel in layer.pixels_mut() {
+            *pixel = Rgba([r, g, b, 0]);
+        }
+        let p = position - origin.to_vec2();
+        drawing::draw_text_mut(
+            layer,
+            to_rgba(style.stroke_color()),
+            p.x.round() as i32,
+            p.y.round() as i32,
+            Scale::uniform(style.font_size),
+            font,
+            text,
+        );
+    });
+}
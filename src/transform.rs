@@ -0,0 +1,148 @@
+use egui::Pos2;
+use image::{imageops, RgbaImage};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+impl ResizeFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResizeFilter::Nearest => "Nearest",
+            ResizeFilter::Bilinear => "Bilinear",
+            ResizeFilter::Bicubic => "Bicubic",
+            ResizeFilter::Lanczos => "Lanczos",
+        }
+    }
+
+    fn filter_type(&self) -> imageops::FilterType {
+        match self {
+            ResizeFilter::Nearest => imageops::FilterType::Nearest,
+            ResizeFilter::Bilinear => imageops::FilterType::Triangle,
+            ResizeFilter::Bicubic => imageops::FilterType::CatmullRom,
+            ResizeFilter::Lanczos => imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+//The working image and its crop as they were before a transform, to undo it
+pub type Snapshot = (RgbaImage, Option<(u32, u32, u32, u32)>);
+
+pub const RESIZE_FILTERS: [ResizeFilter; 4] = [
+    ResizeFilter::Nearest,
+    ResizeFilter::Bilinear,
+    ResizeFilter::Bicubic,
+    ResizeFilter::Lanczos,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResizeSettings {
+    pub by_percent: bool,
+    pub percent: u32,
+    pub width: u32,
+    pub height: u32,
+    pub keep_ratio: bool,
+    pub filter: ResizeFilter,
+}
+
+impl Default for ResizeSettings {
+    fn default() -> Self {
+        ResizeSettings {
+            by_percent: true,
+            percent: 100,
+            width: 0,
+            height: 0,
+            keep_ratio: true,
+            filter: ResizeFilter::Bicubic,
+        }
+    }
+}
+
+impl ResizeSettings {
+    //The size an image `size` large gets resized to
+    pub fn target(&self, size: (u32, u32)) -> (u32, u32) {
+        if self.by_percent {
+            let factor = self.percent as f32 / 100.0;
+            (
+                ((size.0 as f32 * factor).round() as u32).max(1),
+                ((size.1 as f32 * factor).round() as u32).max(1),
+            )
+        } else {
+            (self.width.max(1), self.height.max(1))
+        }
+    }
+}
+
+//A geometric change of the whole image, rotations are clockwise
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Geometry {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Resize(u32, u32, ResizeFilter),
+}
+
+impl Geometry {
+    pub fn apply(&self, image: &RgbaImage) -> RgbaImage {
+        match self {
+            Geometry::Rotate90 => imageops::rotate90(image),
+            Geometry::Rotate180 => imageops::rotate180(image),
+            Geometry::Rotate270 => imageops::rotate270(image),
+            Geometry::FlipHorizontal => imageops::flip_horizontal(image),
+            Geometry::FlipVertical => imageops::flip_vertical(image),
+            Geometry::Resize(w, h, filter) => {
+                imageops::resize(image, (*w).max(1), (*h).max(1), filter.filter_type())
+            }
+        }
+    }
+
+    //Where a point ends up, both given in fractions of the image size:
+    //annotations follow the image this way whatever size it is shown at
+    pub fn map(&self, p: Pos2) -> Pos2 {
+        match self {
+            Geometry::Rotate90 => Pos2::new(1.0 - p.y, p.x),
+            Geometry::Rotate180 => Pos2::new(1.0 - p.x, 1.0 - p.y),
+            Geometry::Rotate270 => Pos2::new(p.y, 1.0 - p.x),
+            Geometry::FlipHorizontal => Pos2::new(1.0 - p.x, p.y),
+            Geometry::FlipVertical => Pos2::new(p.x, 1.0 - p.y),
+            Geometry::Resize(..) => p,
+        }
+    }
+
+    //the change bringing the points back, a resize keeps them where they are
+    pub fn inverse(&self) -> Geometry {
+        match self {
+            Geometry::Rotate90 => Geometry::Rotate270,
+            Geometry::Rotate270 => Geometry::Rotate90,
+            other => *other,
+        }
+    }
+
+    //Moves an area (x, y, width, height) of an image `size` large along with its pixels
+    pub fn map_area(&self, area: (u32, u32, u32, u32), size: (u32, u32)) -> (u32, u32, u32, u32) {
+        let (x, y, w, h) = area;
+        let (width, height) = size;
+        match self {
+            Geometry::Rotate90 => (height - (y + h), x, h, w),
+            Geometry::Rotate180 => (width - (x + w), height - (y + h), w, h),
+            Geometry::Rotate270 => (y, width - (x + w), h, w),
+            Geometry::FlipHorizontal => (width - (x + w), y, w, h),
+            Geometry::FlipVertical => (x, height - (y + h), w, h),
+            Geometry::Resize(new_w, new_h, _) => {
+                let scale_x = *new_w as f32 / width as f32;
+                let scale_y = *new_h as f32 / height as f32;
+                let x0 = (x as f32 * scale_x).round() as u32;
+                let y0 = (y as f32 * scale_y).round() as u32;
+                let x1 = (((x + w) as f32 * scale_x).round() as u32).clamp(x0 + 1, (*new_w).max(x0 + 1));
+                let y1 = (((y + h) as f32 * scale_y).round() as u32).clamp(y0 + 1, (*new_h).max(y0 + 1));
+                (x0.min(new_w.saturating_sub(1)), y0.min(new_h.saturating_sub(1)), x1 - x0, y1 - y0)
+            }
+        }
+    }
+}
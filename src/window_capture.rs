@@ -0,0 +1,220 @@
+use std::error::Error;
+
+use egui::{Pos2, Rect};
+
+use crate::coords;
+
+//A top-level application window, position and size are in physical pixels
+//of the virtual desktop as reported by the X server
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowInfo {
+    pub fn label(&self) -> String {
+        let title = if self.title.trim().is_empty() {
+            format!("Window {:#x}", self.id)
+        } else {
+            self.title.clone()
+        };
+        format!("{} ({}×{})", title, self.width, self.height)
+    }
+
+    //bounds in logical units, converted like display_info does for the monitors
+    pub fn logical_rect(&self, scale_factor: f32) -> Rect {
+        coords::server_rect(self.x, self.y, self.width, self.height, scale_factor)
+    }
+}
+
+//The window under `pos` (logical units), `windows` goes from the topmost one
+pub fn window_at(windows: &[WindowInfo], pos: Pos2, scale_factor: f32) -> Option<&WindowInfo> {
+    windows
+        .iter()
+        .find(|w| w.logical_rect(scale_factor).contains(pos))
+}
+
+//Visible top-level windows from the topmost to the bottom one. Windows whose title
+//is `exclude_title` (the grabber itself) are left out
+#[cfg(target_os = "linux")]
+pub fn list_windows(exclude_title: &str) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, MapState, Window};
+
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen_num].root;
+    let atom = |name: &str| -> Result<u32, Box<dyn Error>> {
+        Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    };
+    let client_list = atom("_NET_CLIENT_LIST_STACKING")?;
+    let net_wm_name = atom("_NET_WM_NAME")?;
+    let utf8_string = atom("UTF8_STRING")?;
+
+    //the window manager keeps the list of the application windows, without one
+    //(e.g. a bare Xvfb) the children of the root window are the application windows
+    let stacking = conn
+        .get_property(false, root, client_list, AtomEnum::WINDOW, 0, u32::MAX)?
+        .reply()?;
+    let candidates: Vec<Window> = match stacking.value32() {
+        Some(ids) if stacking.value_len > 0 => ids.collect(),
+        _ => conn.query_tree(root)?.reply()?.children,
+    };
+
+    let mut windows = Vec::new();
+    //both lists go from the bottom to the top of the stack
+    for id in candidates.into_iter().rev() {
+        let attributes = match conn.get_window_attributes(id)?.reply() {
+            Ok(a) => a,
+            //the window has been closed in the meantime
+            Err(_) => continue,
+        };
+        if attributes.map_state != MapState::VIEWABLE || attributes.override_redirect {
+            continue;
+        }
+        let geometry = conn.get_geometry(id)?.reply()?;
+        if geometry.width == 0 || geometry.height == 0 {
+            continue;
+        }
+        let position = conn.translate_coordinates(id, root, 0, 0)?.reply()?;
+
+        let mut title = conn
+            .get_property(false, id, net_wm_name, utf8_string, 0, 1024)?
+            .reply()?
+            .value;
+        if title.is_empty() {
+            title = conn
+                .get_property(false, id, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)?
+                .reply()?
+                .value;
+        }
+        let title = String::from_utf8_lossy(&title).to_string();
+        if title == exclude_title {
+            continue;
+        }
+
+        windows.push(WindowInfo {
+            id,
+            title,
+            x: position.dst_x as i32,
+            y: position.dst_y as i32,
+            width: geometry.width as u32,
+            height: geometry.height as u32,
+        });
+    }
+    Ok(windows)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn list_windows(_exclude_title: &str) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+    Err("window capture is available only on X11".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Vec2;
+
+    fn window(id: u32, x: i32, y: i32, width: u32, height: u32) -> WindowInfo {
+        WindowInfo {
+            id,
+            title: format!("window {}", id),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn window_at_picks_the_topmost() {
+        //from the topmost: a small window above a larger one
+        let windows = [window(1, 100, 100, 200, 100), window(2, 0, 0, 800, 600)];
+
+        assert_eq!(window_at(&windows, Pos2::new(150.0, 150.0), 1.0).map(|w| w.id), Some(1));
+        assert_eq!(window_at(&windows, Pos2::new(50.0, 50.0), 1.0).map(|w| w.id), Some(2));
+        assert_eq!(window_at(&windows, Pos2::new(900.0, 50.0), 1.0), None);
+    }
+
+    #[test]
+    fn windows_and_monitors_share_their_units() {
+        use crate::monitors::Monitor;
+        use display_info::DisplayInfo;
+
+        //a 2560×1440 monitor at 1.5 left of the primary one, as display_info gives
+        //it from the X server data: every value divided by the scale and truncated
+        let info = DisplayInfo {
+            id: 0,
+            x: -1706,
+            y: 0,
+            width: 1706,
+            height: 960,
+            rotation: 0.0,
+            scale_factor: 1.5,
+            is_primary: false,
+        };
+        let monitor = Monitor::from_display_info(&info);
+        //a small window and a maximized one with the X server geometry of the same monitor
+        let windows = [window(1, -2560 + 150, 60, 450, 300), window(2, -2560, 0, 2560, 1440)];
+
+        assert_eq!(windows[1].logical_rect(monitor.scale_factor), monitor.rect());
+        for (pos, id) in [
+            (monitor.rect().center(), Some(2)),
+            (monitor.rect().min, Some(2)),
+            (monitor.rect().max - Vec2::splat(0.5), Some(2)),
+            (windows[0].logical_rect(monitor.scale_factor).center(), Some(1)),
+            (monitor.rect().max + Vec2::splat(0.5), None),
+        ] {
+            assert_eq!(window_at(&windows, pos, monitor.scale_factor).map(|w| w.id), id, "at {:?}", pos);
+        }
+    }
+
+    //Needs an X server without other windows in the way, e.g.
+    //xvfb-run cargo test -- --ignored list_windows
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs an X server, run it under xvfb-run"]
+    fn list_windows_finds_a_mapped_window() {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, PropMode, WindowClass};
+        use x11rb::wrapper::ConnectionExt as _;
+
+        let (conn, screen_num) = x11rb::connect(None).unwrap();
+        let screen = &conn.setup().roots[screen_num];
+        let id = conn.generate_id().unwrap();
+        conn.create_window(
+            screen.root_depth,
+            id,
+            screen.root,
+            40,
+            30,
+            320,
+            200,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new().background_pixel(screen.white_pixel),
+        )
+        .unwrap();
+        let title = format!("dummy window {}", std::process::id());
+        conn.change_property8(PropMode::REPLACE, id, AtomEnum::WM_NAME, AtomEnum::STRING, title.as_bytes())
+            .unwrap();
+        conn.map_window(id).unwrap();
+        conn.sync().unwrap();
+
+        let windows = list_windows("").unwrap();
+        let excluded = list_windows(&title).unwrap();
+        conn.destroy_window(id).unwrap();
+        conn.sync().unwrap();
+
+        let found = windows.iter().find(|w| w.title == title).expect("the dummy window is not listed");
+        assert_eq!(found.id, id);
+        assert_eq!((found.x, found.y, found.width, found.height), (40, 30, 320, 200));
+        assert_eq!(windows.first().map(|w| w.id), Some(id), "the newest window is the topmost");
+        assert!(excluded.iter().all(|w| w.id != id));
+    }
+}